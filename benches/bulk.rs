@@ -0,0 +1,56 @@
+use criterion::black_box;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use criterion::Throughput;
+
+use colordata::bulk;
+use colordata::space::Hsl;
+use colordata::Color;
+
+/// a 1920x1080 frame of pseudo random pixels
+fn frame() -> Vec<u8> {
+    let mut state = 0x2545_f491u32;
+    (0..1920 * 1080 * 4)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+fn conversions(c: &mut Criterion) {
+    let rgba = frame();
+    let pixels = rgba.len() / 4;
+    let mut colors = vec![Color::new(); pixels];
+    bulk::rgba_to_colors(&rgba, &mut colors);
+
+    let mut group = c.benchmark_group("1080p frame");
+    group.throughput(Throughput::Elements(pixels as u64));
+    group.sample_size(20);
+    let mut gray = vec![0u8; pixels];
+    group.bench_function("rgba to grayscale", |b| {
+        b.iter(|| bulk::rgba_to_grayscale(black_box(&rgba), &mut gray))
+    });
+    group.bench_function("colors to grayscale", |b| {
+        b.iter(|| bulk::to_grayscale_slice(black_box(&colors), &mut gray))
+    });
+    let mut linear = vec![[0.0f32; 4]; pixels];
+    group.bench_function("srgb to linear", |b| {
+        b.iter(|| bulk::srgb_to_linear_slice(black_box(&colors), &mut linear))
+    });
+    let mut hsl = vec![Hsl::default(); pixels];
+    group.bench_function("convert to hsl", |b| {
+        b.iter(|| bulk::convert_slice(black_box(&colors), &mut hsl))
+    });
+    let mut packed = vec![0u8; rgba.len()];
+    group.bench_function("colors to rgba", |b| {
+        b.iter(|| bulk::colors_to_rgba(black_box(&colors), &mut packed))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, conversions);
+criterion_main!(benches);
@@ -0,0 +1,90 @@
+use criterion::black_box;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+
+use colordata::difference::DeltaE;
+use colordata::index::PaletteIndex;
+use colordata::traits::*;
+use colordata::Color;
+
+/// the xterm 256 color palette
+fn xterm_palette() -> Vec<Color> {
+    let mut palette = vec![
+        Color::from_hex("#000000"),
+        Color::from_hex("#cd0000"),
+        Color::from_hex("#00cd00"),
+        Color::from_hex("#cdcd00"),
+        Color::from_hex("#0000ee"),
+        Color::from_hex("#cd00cd"),
+        Color::from_hex("#00cdcd"),
+        Color::from_hex("#e5e5e5"),
+        Color::from_hex("#7f7f7f"),
+        Color::from_hex("#ff0000"),
+        Color::from_hex("#00ff00"),
+        Color::from_hex("#ffff00"),
+        Color::from_hex("#5c5cff"),
+        Color::from_hex("#ff00ff"),
+        Color::from_hex("#00ffff"),
+        Color::from_hex("#ffffff"),
+    ];
+    let levels = [0u8, 95, 135, 175, 215, 255];
+    for red in levels.iter() {
+        for green in levels.iter() {
+            for blue in levels.iter() {
+                palette.push(Color::from_tuple((*red, *green, *blue)));
+            }
+        }
+    }
+    for step in 0..24u8 {
+        let gray = 8 + step * 10;
+        palette.push(Color::from_tuple((gray, gray, gray)));
+    }
+    palette
+}
+
+fn queries() -> Vec<Color> {
+    let mut state = 0x2545_f491u32;
+    (0..1024)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            Color::from_tuple((state as u8, (state >> 8) as u8, (state >> 16) as u8))
+        })
+        .collect()
+}
+
+fn nearest(c: &mut Criterion) {
+    let palette = xterm_palette();
+    let queries = queries();
+    let metrics = [
+        ("oklab", DeltaE::Oklab),
+        ("cie76", DeltaE::Cie76),
+        ("ciede2000", DeltaE::Ciede2000),
+    ];
+    for (name, metric) in metrics.iter() {
+        let index = PaletteIndex::new(&palette, *metric);
+        let mut position = 0;
+        c.bench_function(&format!("nearest of 256 ({})", name), |b| {
+            b.iter(|| {
+                position = (position + 1) % queries.len();
+                index.nearest(black_box(queries[position]))
+            })
+        });
+    }
+    let index = PaletteIndex::new(&palette, DeltaE::Oklab);
+    c.bench_function("build 256 (oklab)", |b| {
+        b.iter(|| PaletteIndex::new(black_box(&palette), DeltaE::Oklab))
+    });
+    let mut position = 0;
+    c.bench_function("4 nearest of 256 (oklab)", |b| {
+        b.iter(|| {
+            position = (position + 1) % queries.len();
+            index.k_nearest(black_box(queries[position]), 4)
+        })
+    });
+}
+
+criterion_group!(benches, nearest);
+criterion_main!(benches);
@@ -0,0 +1,766 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use once_cell::sync::Lazy;
+
+use crate::difference::oklab;
+use crate::scheme::TerminalScheme;
+use crate::space::Oklab;
+use crate::traits::*;
+use crate::Color;
+
+/// how many colors a terminal can show, ordered from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorSupport {
+    None,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// guess from `NO_COLOR`, `COLORTERM` and `TERM`
+    pub fn detect() -> ColorSupport {
+        ColorSupport::detect_with(|name| std::env::var(name).ok())
+    }
+
+    fn detect_with<F: Fn(&str) -> Option<String>>(var: F) -> ColorSupport {
+        if var("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return ColorSupport::None;
+        }
+        if let Some(colorterm) = var("COLORTERM") {
+            if colorterm == "truecolor" || colorterm == "24bit" {
+                return ColorSupport::TrueColor;
+            }
+        }
+        match var("TERM") {
+            None => ColorSupport::None,
+            Some(term) if term.is_empty() || term == "dumb" => ColorSupport::None,
+            Some(term) if term.ends_with("-direct") => ColorSupport::TrueColor,
+            Some(term) if term.contains("256color") => ColorSupport::Ansi256,
+            Some(_) => ColorSupport::Ansi16,
+        }
+    }
+}
+
+/// xterm's default 16 colors
+const ANSI16: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0xcd, 0x00, 0x00),
+    (0x00, 0xcd, 0x00),
+    (0xcd, 0xcd, 0x00),
+    (0x00, 0x00, 0xee),
+    (0xcd, 0x00, 0xcd),
+    (0x00, 0xcd, 0xcd),
+    (0xe5, 0xe5, 0xe5),
+    (0x7f, 0x7f, 0x7f),
+    (0xff, 0x00, 0x00),
+    (0x00, 0xff, 0x00),
+    (0xff, 0xff, 0x00),
+    (0x5c, 0x5c, 0xff),
+    (0xff, 0x00, 0xff),
+    (0x00, 0xff, 0xff),
+    (0xff, 0xff, 0xff),
+];
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+static ANSI256_OKLAB: Lazy<Vec<Oklab>> = Lazy::new(|| {
+    (0..=255u8)
+        .map(|index| Oklab::from(ansi256_color(index)))
+        .collect()
+});
+
+/// the color xterm shows for palette `index` by default
+/// 0 - 15 are the ansi colors, 16 - 231 the 6x6x6 cube and 232 - 255 the gray ramp
+pub fn ansi256_color(index: u8) -> Color {
+    match index {
+        0..=15 => Color::from_tuple(ANSI16[index as usize]),
+        16..=231 => {
+            let cube = index - 16;
+            Color::from_tuple((
+                CUBE_LEVELS[(cube / 36) as usize],
+                CUBE_LEVELS[(cube / 6 % 6) as usize],
+                CUBE_LEVELS[(cube % 6) as usize],
+            ))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            Color::from_tuple((gray, gray, gray))
+        }
+    }
+}
+
+/// index in `range` closest to `color` in oklab
+fn nearest(color: Color, range: std::ops::RangeInclusive<u8>) -> u8 {
+    let target = Oklab::from(color);
+    range
+        .map(|index| (index, oklab(target, ANSI256_OKLAB[index as usize])))
+        .fold((0u8, f32::INFINITY), |best, candidate| {
+            match candidate.1 < best.1 {
+                true => candidate,
+                false => best,
+            }
+        })
+        .0
+}
+
+/// sgr parameters selecting `color` at `support`, foreground unless `background`
+fn sgr_parameters(color: Color, support: ColorSupport, background: bool) -> Option<String> {
+    let base = match background {
+        true => 48,
+        false => 38,
+    };
+    match support {
+        ColorSupport::None => None,
+        ColorSupport::Ansi16 => {
+            let index = color.to_ansi16();
+            let offset = match index < 8 {
+                true => base - 8,
+                false => base + 52,
+            };
+            Some((offset + index % 8).to_string())
+        }
+        ColorSupport::Ansi256 => Some(format!("{};5;{}", base, color.to_ansi256())),
+        ColorSupport::TrueColor => Some(format!(
+            "{};2;{};{};{}",
+            base, color.red, color.green, color.blue
+        )),
+    }
+}
+
+impl Color {
+    /// nearest xterm-256 cube or gray ramp index, the ansi 0 - 15 are skipped
+    /// since terminals let users change them
+    pub fn to_ansi256(&self) -> u8 {
+        nearest(*self, 16..=255)
+    }
+
+    /// nearest of the 16 ansi colors, assuming xterm's defaults
+    pub fn to_ansi16(&self) -> u8 {
+        nearest(*self, 0..=15)
+    }
+
+    /// sgr sequence setting the foreground, empty for `ColorSupport::None`
+    pub fn ansi_fg(&self, support: ColorSupport) -> String {
+        sgr_parameters(*self, support, false)
+            .map(|parameters| format!("\x1b[{}m", parameters))
+            .unwrap_or_default()
+    }
+
+    /// sgr sequence setting the background, empty for `ColorSupport::None`
+    pub fn ansi_bg(&self, support: ColorSupport) -> String {
+        sgr_parameters(*self, support, true)
+            .map(|parameters| format!("\x1b[{}m", parameters))
+            .unwrap_or_default()
+    }
+
+    /// `text` in this foreground color, as truecolor until `support` says otherwise
+    pub fn paint<T: Display>(&self, text: T) -> Painted<T> {
+        Painted {
+            text,
+            foreground: Some(*self),
+            background: None,
+            support: ColorSupport::TrueColor,
+        }
+    }
+}
+
+/// displays `text` wrapped in sgr sequences and a reset
+/// nothing is added when there is no color or the support is `None`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Painted<T> {
+    pub text: T,
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub support: ColorSupport,
+}
+
+impl<T: Display> Painted<T> {
+    pub fn new(text: T) -> Self {
+        Painted {
+            text,
+            foreground: None,
+            background: None,
+            support: ColorSupport::TrueColor,
+        }
+    }
+
+    pub fn fg(mut self, color: Color) -> Self {
+        self.foreground = Some(color);
+        self
+    }
+
+    pub fn on(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    /// downgrade to what the terminal can show
+    pub fn support(mut self, support: ColorSupport) -> Self {
+        self.support = support;
+        self
+    }
+}
+
+impl<T: Display> Display for Painted<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let parameters: Vec<String> = vec![
+            self.foreground
+                .and_then(|color| sgr_parameters(color, self.support, false)),
+            self.background
+                .and_then(|color| sgr_parameters(color, self.support, true)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if parameters.is_empty() {
+            return write!(f, "{}", self.text);
+        }
+        write!(f, "\x1b[{}m{}\x1b[0m", parameters.join(";"), self.text)
+    }
+}
+
+/// the colors sgr indices resolve to, xterm's defaults unless changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AnsiPalette {
+    pub colors: [Color; 256],
+}
+
+impl Default for AnsiPalette {
+    fn default() -> Self {
+        let mut colors = [Color::new(); 256];
+        for (index, color) in colors.iter_mut().enumerate() {
+            *color = ansi256_color(index as u8);
+        }
+        AnsiPalette { colors }
+    }
+}
+
+impl AnsiPalette {
+    /// xterm's cube and gray ramp with the scheme's color0 - color15
+    pub fn from_scheme(scheme: &TerminalScheme) -> Self {
+        let mut palette = AnsiPalette::default();
+        palette.colors[..16].copy_from_slice(&scheme.colors);
+        palette
+    }
+
+    /// the styled text runs in `input`, see `Spans`
+    pub fn spans<'a>(&'a self, input: &'a [u8]) -> Spans<'a> {
+        Spans {
+            palette: self,
+            input,
+            position: 0,
+            foreground: None,
+            background: None,
+        }
+    }
+
+    /// applies one sgr parameter list to `foreground` and `background`,
+    /// attributes other than color are ignored
+    fn apply_sgr(
+        &self,
+        parameters: &[u8],
+        foreground: &mut Option<Color>,
+        background: &mut Option<Color>,
+    ) {
+        let text = String::from_utf8_lossy(parameters);
+        let parameters: Vec<&str> = text.split(';').collect();
+        let mut index = 0usize;
+        while index < parameters.len() {
+            let parameter = parameters[index];
+            index += 1;
+            // colon sub-parameters carry the whole extended color in one parameter
+            if parameter.contains(':') {
+                let parts: Vec<&str> = parameter.split(':').collect();
+                let target = match parts[0] {
+                    "38" => &mut *foreground,
+                    "48" => &mut *background,
+                    _ => continue,
+                };
+                if let Some(color) = self.extended(&parts[1..], true) {
+                    *target = Some(color);
+                }
+                continue;
+            }
+            let code: u16 = match parameter {
+                "" => 0,
+                _ => match parameter.parse() {
+                    Ok(code) => code,
+                    Err(_) => continue,
+                },
+            };
+            match code {
+                0 => {
+                    *foreground = None;
+                    *background = None;
+                }
+                30..=37 => *foreground = Some(self.colors[(code - 30) as usize]),
+                90..=97 => *foreground = Some(self.colors[(code - 82) as usize]),
+                39 => *foreground = None,
+                40..=47 => *background = Some(self.colors[(code - 40) as usize]),
+                100..=107 => *background = Some(self.colors[(code - 92) as usize]),
+                49 => *background = None,
+                38 | 48 => {
+                    let consumed = match parameters.get(index) {
+                        Some(&"5") => 2,
+                        Some(&"2") => 4,
+                        _ => 1,
+                    };
+                    let end = (index + consumed).min(parameters.len());
+                    let color = self.extended(&parameters[index..end], false);
+                    index = end;
+                    if let Some(color) = color {
+                        match code {
+                            38 => *foreground = Some(color),
+                            _ => *background = Some(color),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// `5;n` or `2;r;g;b` after a 38 or 48, with colons the truecolor form
+    /// may also carry a color space id before the channels
+    fn extended(&self, parts: &[&str], colon: bool) -> Option<Color> {
+        let numbers: Vec<Option<u8>> = parts.iter().map(|part| part.parse().ok()).collect();
+        match numbers.as_slice() {
+            [Some(5), Some(index)] => Some(self.colors[*index as usize]),
+            [Some(2), _, Some(red), Some(green), Some(blue)] if colon => {
+                Some(Color::from_tuple((*red, *green, *blue)))
+            }
+            [Some(2), Some(red), Some(green), Some(blue)] => {
+                Some(Color::from_tuple((*red, *green, *blue)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// a run of text drawn with the same colors, `None` is the terminal's default
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub text: String,
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+}
+
+/// walks an ansi colored byte stream, sgr sequences update the colors and
+/// every other escape sequence is dropped, consecutive text with the same
+/// colors is one span and invalid utf-8 is replaced
+pub struct Spans<'a> {
+    palette: &'a AnsiPalette,
+    input: &'a [u8],
+    position: usize,
+    foreground: Option<Color>,
+    background: Option<Color>,
+}
+
+impl<'a> Spans<'a> {
+    /// length of the escape sequence at `start`, and the sgr parameters if it is one
+    fn escape(&self, start: usize) -> (usize, Option<&'a [u8]>) {
+        let input = self.input;
+        match input.get(start + 1) {
+            Some(b'[') => {
+                let parameters = start + 2;
+                match input[parameters..]
+                    .iter()
+                    .position(|byte| (0x40..=0x7e).contains(byte))
+                {
+                    Some(offset) => {
+                        let end = parameters + offset;
+                        let sgr = match input[end] {
+                            b'm' => Some(&input[parameters..end]),
+                            _ => None,
+                        };
+                        (end + 1 - start, sgr)
+                    }
+                    None => (input.len() - start, None),
+                }
+            }
+            // osc, dcs, apc and pm run until bel or st
+            Some(b']') | Some(b'P') | Some(b'_') | Some(b'^') => {
+                let mut end = start + 2;
+                while end < input.len() {
+                    match input[end] {
+                        0x07 => return (end + 1 - start, None),
+                        0x1b if input.get(end + 1) == Some(&b'\\') => {
+                            return (end + 2 - start, None)
+                        }
+                        _ => end += 1,
+                    }
+                }
+                (input.len() - start, None)
+            }
+            Some(_) => (2, None),
+            None => (1, None),
+        }
+    }
+}
+
+impl<'a> Iterator for Spans<'a> {
+    type Item = Span;
+
+    fn next(&mut self) -> Option<Span> {
+        let mut text = Vec::new();
+        let mut colors = (self.foreground, self.background);
+        while self.position < self.input.len() {
+            let start = self.position;
+            if self.input[start] != 0x1b {
+                self.position = self.input[start..]
+                    .iter()
+                    .position(|byte| *byte == 0x1b)
+                    .map_or(self.input.len(), |offset| start + offset);
+                text.extend_from_slice(&self.input[start..self.position]);
+                continue;
+            }
+            let (length, sgr) = self.escape(start);
+            self.position += length;
+            if let Some(parameters) = sgr {
+                self.palette
+                    .apply_sgr(parameters, &mut self.foreground, &mut self.background);
+            }
+            if (self.foreground, self.background) != colors {
+                if !text.is_empty() {
+                    break;
+                }
+                colors = (self.foreground, self.background);
+            }
+        }
+        match text.is_empty() {
+            true => None,
+            false => Some(Span {
+                text: String::from_utf8_lossy(&text).into_owned(),
+                foreground: colors.0,
+                background: colors.1,
+            }),
+        }
+    }
+}
+
+/// a terminal's answer to an osc color query
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OscReply {
+    /// osc 4, a palette entry
+    Palette(u8, Color),
+    /// osc 10
+    Foreground(Color),
+    /// osc 11
+    Background(Color),
+    /// osc 12
+    Cursor(Color),
+}
+
+/// `reply` as sent back for `\x1b]11;?\x07` and friends, terminated by bel or st
+/// the leading escape and the terminator are optional
+pub fn parse_osc_reply(reply: &str) -> Result<OscReply, Box<dyn Error>> {
+    let body = reply.strip_prefix("\x1b]").unwrap_or(reply);
+    let body = body
+        .strip_suffix('\x07')
+        .or_else(|| body.strip_suffix("\x1b\\"))
+        .unwrap_or(body);
+    let (command, rest) = body
+        .split_once(';')
+        .ok_or_else(|| format!("Invalid osc reply: {:?}", reply))?;
+    match command {
+        "4" => {
+            let (index, spec) = rest
+                .split_once(';')
+                .ok_or_else(|| format!("Invalid osc reply: {:?}", reply))?;
+            let index = index
+                .parse()
+                .map_err(|_| format!("Invalid palette index: {}", index))?;
+            Ok(OscReply::Palette(index, parse_x11_color(spec)?))
+        }
+        "10" => Ok(OscReply::Foreground(parse_x11_color(rest)?)),
+        "11" => Ok(OscReply::Background(parse_x11_color(rest)?)),
+        "12" => Ok(OscReply::Cursor(parse_x11_color(rest)?)),
+        _ => Err(format!("Unsupported osc reply: {:?}", reply).into()),
+    }
+}
+
+/// `hex` out of 1 - 4 digits scaled to 0 - 255
+fn scaled_channel(hex: &str) -> Option<u8> {
+    if hex.is_empty() || hex.len() > 4 {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let max = (1u32 << (4 * hex.len())) - 1;
+    Some(((value * 255 + max / 2) / max) as u8)
+}
+
+/// the x11 color specs terminals answer with, `rgb:r/g/b` and `rgba:r/g/b/a`
+/// with 1 - 4 hex digits a channel, and the legacy `#rgb` to `#rrrrggggbbbb`
+/// where, as in `XParseColor`, the digits are the high bits of each channel
+pub fn parse_x11_color(spec: &str) -> Result<Color, Box<dyn Error>> {
+    let spec = spec.trim();
+    let invalid = || format!("Invalid x11 color: {}", spec);
+    if let Some(hex) = spec.strip_prefix('#') {
+        let width = hex.len() / 3;
+        if !(1..=4).contains(&width)
+            || hex.len() % 3 != 0
+            || !hex.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return Err(invalid().into());
+        }
+        let channel = |index: usize| {
+            let digits = &hex[index * width..(index + 1) * width];
+            let value = u32::from_str_radix(digits, 16).unwrap() << (4 * (4 - width));
+            (value >> 8) as u8
+        };
+        return Ok(Color::from_tuple((channel(0), channel(1), channel(2))));
+    }
+    let (count, channels) = match spec.split_once(':') {
+        Some(("rgb", channels)) => (3, channels),
+        Some(("rgba", channels)) => (4, channels),
+        _ => return Err(invalid().into()),
+    };
+    let channels: Vec<u8> = channels
+        .split('/')
+        .map(scaled_channel)
+        .collect::<Option<_>>()
+        .filter(|channels: &Vec<u8>| channels.len() == count)
+        .ok_or_else(invalid)?;
+    Ok(Color::from_tuple_alpha((
+        channels[0],
+        channels[1],
+        channels[2],
+        channels.get(3).copied().unwrap_or(255),
+    )))
+}
+
+mod tests {
+    #[test]
+    fn ansi256_tests() {
+        use super::*;
+        assert_eq!(ansi256_color(1), Color::from_hex("#cd0000"));
+        assert_eq!(ansi256_color(16), Color::from_hex("#000000"));
+        assert_eq!(ansi256_color(196), Color::from_hex("#ff0000"));
+        assert_eq!(ansi256_color(231), Color::from_hex("#ffffff"));
+        assert_eq!(ansi256_color(232), Color::from_hex("#080808"));
+        assert_eq!(ansi256_color(255), Color::from_hex("#eeeeee"));
+        for index in 16..=255u8 {
+            assert_eq!(ansi256_color(index).to_ansi256(), index);
+        }
+        assert_eq!(Color::from_hex("#ff0001").to_ansi256(), 196);
+        assert_eq!(Color::from_hex("#767676").to_ansi256(), 243);
+        // the cube has no level between 0 and 95, the gray ramp does
+        assert_eq!(Color::from_hex("#303030").to_ansi256(), 236);
+        assert_eq!(Color::from_hex("#fe0000").to_ansi16(), 9);
+        assert_eq!(Color::from_hex("#101010").to_ansi16(), 0);
+        assert_eq!(Color::from_hex("#5a5aff").to_ansi16(), 12);
+    }
+
+    #[test]
+    fn sgr_tests() {
+        use super::*;
+        let color = Color::from_hex("#ff8000");
+        assert_eq!(
+            color.ansi_fg(ColorSupport::TrueColor),
+            "\x1b[38;2;255;128;0m"
+        );
+        assert_eq!(
+            color.ansi_bg(ColorSupport::TrueColor),
+            "\x1b[48;2;255;128;0m"
+        );
+        assert_eq!(color.ansi_fg(ColorSupport::Ansi256), "\x1b[38;5;208m");
+        assert_eq!(color.ansi_bg(ColorSupport::Ansi256), "\x1b[48;5;208m");
+        assert_eq!(
+            Color::from_hex("#cd0000").ansi_fg(ColorSupport::Ansi16),
+            "\x1b[31m"
+        );
+        assert_eq!(
+            Color::from_hex("#cd0000").ansi_bg(ColorSupport::Ansi16),
+            "\x1b[41m"
+        );
+        assert_eq!(
+            Color::from_hex("#ffffff").ansi_fg(ColorSupport::Ansi16),
+            "\x1b[97m"
+        );
+        assert_eq!(
+            Color::from_hex("#ffffff").ansi_bg(ColorSupport::Ansi16),
+            "\x1b[107m"
+        );
+        assert_eq!(color.ansi_fg(ColorSupport::None), "");
+    }
+
+    #[test]
+    fn painted_tests() {
+        use super::*;
+        let red = Color::from_hex("#ff0000");
+        let blue = Color::from_hex("#0000ff");
+        assert_eq!(red.paint("hi").to_string(), "\x1b[38;2;255;0;0mhi\x1b[0m");
+        assert_eq!(
+            red.paint(42)
+                .on(blue)
+                .support(ColorSupport::Ansi256)
+                .to_string(),
+            "\x1b[38;5;196;48;5;21m42\x1b[0m"
+        );
+        assert_eq!(
+            Painted::new("x")
+                .on(blue)
+                .support(ColorSupport::Ansi16)
+                .to_string(),
+            "\x1b[44mx\x1b[0m"
+        );
+        assert_eq!(
+            red.paint("plain").support(ColorSupport::None).to_string(),
+            "plain"
+        );
+        assert_eq!(Painted::new("plain").to_string(), "plain");
+    }
+
+    #[test]
+    fn detect_tests() {
+        use super::*;
+        let vars = |pairs: Vec<(&'static str, &'static str)>| {
+            move |name: &str| {
+                pairs
+                    .iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let detect = |pairs| ColorSupport::detect_with(vars(pairs));
+        assert_eq!(detect(vec![]), ColorSupport::None);
+        assert_eq!(detect(vec![("TERM", "dumb")]), ColorSupport::None);
+        assert_eq!(detect(vec![("TERM", "xterm")]), ColorSupport::Ansi16);
+        assert_eq!(
+            detect(vec![("TERM", "xterm-256color")]),
+            ColorSupport::Ansi256
+        );
+        assert_eq!(
+            detect(vec![("TERM", "xterm-direct")]),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            detect(vec![("TERM", "xterm-256color"), ("COLORTERM", "truecolor")]),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            detect(vec![("COLORTERM", "24bit"), ("NO_COLOR", "1")]),
+            ColorSupport::None
+        );
+        assert_eq!(
+            detect(vec![("TERM", "xterm"), ("NO_COLOR", "")]),
+            ColorSupport::Ansi16
+        );
+        assert!(ColorSupport::Ansi256 > ColorSupport::Ansi16);
+    }
+
+    #[test]
+    fn spans_tests() {
+        use super::*;
+        let palette = AnsiPalette::default();
+        let input = b"plain \x1b[38;2;255;0;0mred\x1b[1m still\x1b[48;5;21m on blue\x1b[39m\x1b]0;title\x07 default\x1b[0m\x1b[91m!\x1b[K";
+        let spans: Vec<Span> = palette.spans(input).collect();
+        let red = Color::from_hex("#ff0000");
+        let blue = Color::from_hex("#0000ff");
+        assert_eq!(
+            spans,
+            vec![
+                Span {
+                    text: "plain ".to_string(),
+                    foreground: None,
+                    background: None,
+                },
+                Span {
+                    text: "red still".to_string(),
+                    foreground: Some(red),
+                    background: None,
+                },
+                Span {
+                    text: " on blue".to_string(),
+                    foreground: Some(red),
+                    background: Some(blue),
+                },
+                Span {
+                    text: " default".to_string(),
+                    foreground: None,
+                    background: Some(blue),
+                },
+                Span {
+                    text: "!".to_string(),
+                    foreground: Some(red),
+                    background: None,
+                },
+            ]
+        );
+
+        let mut scheme = crate::scheme::TerminalScheme::default();
+        scheme.colors[1] = Color::from_hex("#aa0000");
+        scheme.colors[12] = Color::from_hex("#5555ff");
+        let palette = AnsiPalette::from_scheme(&scheme);
+        let spans: Vec<Span> = palette
+            .spans(b"\x1b[31;104ma\x1b[38:5:196mb\x1b[38:2::1:2:3mc\x1b[38:2:4:5:6md\x1b[me")
+            .collect();
+        assert_eq!(spans[0].foreground, Some(Color::from_hex("#aa0000")));
+        assert_eq!(spans[0].background, Some(Color::from_hex("#5555ff")));
+        assert_eq!(spans[1].foreground, Some(red));
+        assert_eq!(spans[2].foreground, Some(Color::from_hex("#010203")));
+        assert_eq!(spans[3].foreground, Some(Color::from_hex("#040506")));
+        assert_eq!(spans[4].foreground, None);
+        assert_eq!(spans[4].background, None);
+
+        // a malformed extended color leaves the rest of the sequence intact
+        let spans: Vec<Span> = palette.spans(b"\x1b[38;5;300;42mx").collect();
+        assert_eq!(spans[0].foreground, None);
+        assert_eq!(spans[0].background, Some(palette.colors[2]));
+        assert!(palette.spans(b"\x1b[31m\x1b[0m\x1b[").next().is_none());
+        assert_eq!(
+            palette.spans(b"caf\xc3\xa9 \xff").next().unwrap().text,
+            "caf\u{e9} \u{fffd}"
+        );
+    }
+
+    #[test]
+    fn osc_tests() {
+        use super::*;
+        assert_eq!(
+            parse_osc_reply("\x1b]11;rgb:1e1e/1e1e/2e2e\x07").unwrap(),
+            OscReply::Background(Color::from_hex("#1e1e2e"))
+        );
+        assert_eq!(
+            parse_osc_reply("\x1b]10;rgb:ffff/8080/0000\x1b\\").unwrap(),
+            OscReply::Foreground(Color::from_hex("#ff8000"))
+        );
+        assert_eq!(
+            parse_osc_reply("\x1b]4;196;rgb:ff/00/00\x07").unwrap(),
+            OscReply::Palette(196, Color::from_hex("#ff0000"))
+        );
+        assert_eq!(
+            parse_osc_reply("12;#ff0000").unwrap(),
+            OscReply::Cursor(Color::from_hex("#ff0000"))
+        );
+        assert!(parse_osc_reply("\x1b]11;?\x07").is_err());
+        assert!(parse_osc_reply("\x1b]4;256;rgb:0/0/0\x07").is_err());
+        assert!(parse_osc_reply("\x1b]52;c;aGk=\x07").is_err());
+
+        assert_eq!(
+            parse_x11_color("rgb:f/8/0").unwrap(),
+            Color::from_hex("#ff8800")
+        );
+        assert_eq!(
+            parse_x11_color("rgb:fff/800/000").unwrap(),
+            Color::from_hex("#ff8000")
+        );
+        assert_eq!(
+            parse_x11_color("rgba:ffff/0000/0000/8080").unwrap(),
+            Color::from_hex8("#ff000080")
+        );
+        assert_eq!(parse_x11_color("#f80").unwrap(), Color::from_hex("#f08000"));
+        assert_eq!(
+            parse_x11_color("#ffff80800000").unwrap(),
+            Color::from_hex("#ff8000")
+        );
+        assert!(parse_x11_color("rgb:fffff/0/0").is_err());
+        assert!(parse_x11_color("rgb:f/f").is_err());
+        assert!(parse_x11_color("rgba:f/f/f").is_err());
+        assert!(parse_x11_color("#ffff").is_err());
+        assert!(parse_x11_color("red").is_err());
+    }
+}
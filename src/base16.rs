@@ -0,0 +1,314 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use crate::scheme::TerminalScheme;
+use crate::traits::*;
+use crate::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum System {
+    Base16,
+    Base24,
+}
+
+impl System {
+    pub fn slot_count(&self) -> usize {
+        match self {
+            System::Base16 => 16,
+            System::Base24 => 24,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            System::Base16 => "base16",
+            System::Base24 => "base24",
+        }
+    }
+}
+
+/// a base16 or base24 scheme, only the first 16 `colors` are meaningful for base16
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Base16Scheme {
+    pub system: System,
+    pub name: String,
+    pub author: String,
+    /// `dark` or `light` when the file says so
+    pub variant: Option<String>,
+    pub colors: [Color; 24],
+}
+
+/// the slots a scheme file did not define, as `base0C` style names
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MissingSlots(pub Vec<String>);
+
+impl Display for MissingSlots {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Missing slots: {}", self.0.join(", "))
+    }
+}
+
+impl Error for MissingSlots {}
+
+/// `base00` - `base0F` then `base10` - `base17`
+pub fn slot_name(index: usize) -> String {
+    format!("base{:02X}", index)
+}
+
+/// a yaml scalar without its quotes or trailing comment
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    for quote in ['"', '\''].iter() {
+        if let Some(rest) = value.strip_prefix(*quote) {
+            let mut result = String::new();
+            let mut characters = rest.chars().peekable();
+            while let Some(character) = characters.next() {
+                match character {
+                    '\\' if *quote == '"' => result.extend(characters.next()),
+                    '\'' if *quote == '\'' && characters.peek() == Some(&'\'') => {
+                        result.extend(characters.next())
+                    }
+                    _ if character == *quote => break,
+                    _ => result.push(character),
+                }
+            }
+            return result;
+        }
+    }
+    let end = value
+        .char_indices()
+        .find(|(position, character)| {
+            *character == '#' && value[..*position].ends_with(char::is_whitespace)
+        })
+        .map_or(value.len(), |(position, _)| position);
+    value[..end].trim().to_string()
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Base16Scheme {
+    /// reads the legacy flat layout (`scheme`, `author`, `base00`, ...) and the
+    /// tinted-theming layout (`system`, `name`, `palette:` with nested slots),
+    /// slot values may be quoted and with or without `#`
+    /// the system is base24 when the file says so or defines any of `base10` -
+    /// `base17`, a `MissingSlots` error lists every slot that is absent
+    pub fn parse(s: &str) -> Result<Base16Scheme, Box<dyn Error>> {
+        let mut system = None;
+        let mut name = String::new();
+        let mut author = String::new();
+        let mut variant = None;
+        let mut slots: [Option<Color>; 24] = [None; 24];
+
+        for line in s.lines() {
+            if line.trim_start().starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once(':') {
+                Some(pair) => pair,
+                None => continue,
+            };
+            let value = unquote(value);
+            match key.trim() {
+                "scheme" | "name" => name = value,
+                "author" => author = value,
+                "variant" => variant = Some(value),
+                "system" => {
+                    system = match value.as_str() {
+                        "base16" => Some(System::Base16),
+                        "base24" => Some(System::Base24),
+                        _ => return Err(format!("Unsupported system: {}", value).into()),
+                    }
+                }
+                key => {
+                    let index = key
+                        .strip_prefix("base")
+                        .filter(|digits| digits.len() == 2)
+                        .and_then(|digits| usize::from_str_radix(digits, 16).ok())
+                        .filter(|index| *index < 24);
+                    if let Some(index) = index {
+                        let hex = format!("#{}", value.trim_start_matches('#'));
+                        let color = Some(hex)
+                            .filter(|hex| hex.len() == 7)
+                            .and_then(|hex| hex.parse::<Color>().ok())
+                            .ok_or_else(|| format!("Invalid color for {}: {}", key, value))?;
+                        slots[index] = Some(color);
+                    }
+                }
+            }
+        }
+
+        let system = system.unwrap_or(match slots[16..].iter().any(Option::is_some) {
+            true => System::Base24,
+            false => System::Base16,
+        });
+        let missing: Vec<String> = (0..system.slot_count())
+            .filter(|index| slots[*index].is_none())
+            .map(slot_name)
+            .collect();
+        if !missing.is_empty() {
+            return Err(Box::new(MissingSlots(missing)));
+        }
+        let mut colors = [Color::new(); 24];
+        for (color, slot) in colors.iter_mut().zip(slots.iter()) {
+            *color = slot.unwrap_or_default();
+        }
+        Ok(Base16Scheme {
+            system,
+            name,
+            author,
+            variant,
+            colors,
+        })
+    }
+
+    /// the slots the system defines
+    pub fn slots(&self) -> &[Color] {
+        &self.colors[..self.system.slot_count()]
+    }
+
+    /// the tinted-theming layout with `#rrggbb` slots under `palette:`
+    pub fn to_yaml(&self) -> String {
+        let mut lines = vec![
+            format!("system: {}", quote(self.system.name())),
+            format!("name: {}", quote(&self.name)),
+            format!("author: {}", quote(&self.author)),
+        ];
+        if let Some(variant) = &self.variant {
+            lines.push(format!("variant: {}", quote(variant)));
+        }
+        lines.push("palette:".to_string());
+        for (index, color) in self.slots().iter().enumerate() {
+            lines.push(format!("  {}: {}", slot_name(index), quote(&color.hex())));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// the ansi layout used by base16-shell, or the base24 one where the
+    /// bright colors come from `base12` - `base17`
+    pub fn to_terminal_scheme(&self) -> TerminalScheme {
+        let layout: [usize; 16] = match self.system {
+            System::Base16 => [
+                0x00, 0x08, 0x0B, 0x0A, 0x0D, 0x0E, 0x0C, 0x05, 0x03, 0x08, 0x0B, 0x0A, 0x0D, 0x0E,
+                0x0C, 0x07,
+            ],
+            System::Base24 => [
+                0x00, 0x08, 0x0B, 0x0A, 0x0D, 0x0E, 0x0C, 0x06, 0x02, 0x12, 0x14, 0x13, 0x16, 0x17,
+                0x15, 0x07,
+            ],
+        };
+        let mut colors = [Color::new(); 16];
+        for (color, slot) in colors.iter_mut().zip(layout.iter()) {
+            *color = self.colors[*slot];
+        }
+        TerminalScheme {
+            background: self.colors[0x00],
+            foreground: self.colors[0x05],
+            cursor: self.colors[0x05],
+            colors,
+        }
+    }
+}
+
+mod tests {
+    #[test]
+    fn legacy_tests() {
+        use super::*;
+        let yaml = r#"scheme: "Default Dark"
+author: "Chris Kempson (http://chriskempson.com)"
+base00: "181818"
+base01: "282828"
+base02: "383838"
+base03: "585858"
+base04: "b8b8b8"
+base05: "d8d8d8"
+base06: "e8e8e8"
+base07: "f8f8f8"
+base08: "ab4642"
+base09: "dc9656"
+base0A: "f7ca88"
+base0B: "a1b56c"
+base0C: "86c1b9"
+base0D: "7cafc2"
+base0E: "ba8baf"
+base0F: a16946 # unquoted
+"#;
+        let scheme = Base16Scheme::parse(yaml).unwrap();
+        assert_eq!(scheme.system, System::Base16);
+        assert_eq!(scheme.name, "Default Dark");
+        assert_eq!(scheme.author, "Chris Kempson (http://chriskempson.com)");
+        assert_eq!(scheme.slots().len(), 16);
+        assert_eq!(scheme.colors[0x0A], Color::from_hex("#f7ca88"));
+        assert_eq!(scheme.colors[0x0F], Color::from_hex("#a16946"));
+
+        let quoted = Base16Scheme::parse(
+            &yaml
+                .replace("\"Default Dark\"", "'It''s Dark' # comment")
+                .replace(
+                    "\"Chris Kempson (http://chriskempson.com)\"",
+                    "'O''Brien'''",
+                ),
+        )
+        .unwrap();
+        assert_eq!(quoted.name, "It's Dark");
+        assert_eq!(quoted.author, "O'Brien'");
+        assert_eq!(Base16Scheme::parse(&quoted.to_yaml()).unwrap(), quoted);
+
+        let terminal = scheme.to_terminal_scheme();
+        assert_eq!(terminal.background, Color::from_hex("#181818"));
+        assert_eq!(terminal.foreground, Color::from_hex("#d8d8d8"));
+        assert_eq!(terminal.colors[1], Color::from_hex("#ab4642"));
+        assert_eq!(terminal.colors[8], Color::from_hex("#585858"));
+        assert_eq!(terminal.colors[15], Color::from_hex("#f8f8f8"));
+
+        let error = Base16Scheme::parse(
+            &yaml
+                .replace("base0C", "# base0C")
+                .replace("base03", "base3"),
+        )
+        .unwrap_err();
+        let missing = error.downcast_ref::<MissingSlots>().unwrap();
+        assert_eq!(missing.0, vec!["base03", "base0C"]);
+        assert_eq!(error.to_string(), "Missing slots: base03, base0C");
+        assert!(Base16Scheme::parse(&yaml.replace("ab4642", "ab46")).is_err());
+    }
+    #[test]
+    fn base24_tests() {
+        use super::*;
+        let mut yaml = String::from(
+            "system: \"base24\"\nname: 'One \"Dark\"'\nauthor: \"someone\"\nvariant: \"dark\"\npalette:\n",
+        );
+        for index in 0..24 {
+            yaml.push_str(&format!(
+                "  {}: \"#{:02x}{:02x}40\"\n",
+                slot_name(index),
+                index,
+                index * 8
+            ));
+        }
+        let scheme = Base16Scheme::parse(&yaml).unwrap();
+        assert_eq!(scheme.system, System::Base24);
+        assert_eq!(scheme.name, "One \"Dark\"");
+        assert_eq!(scheme.variant.as_deref(), Some("dark"));
+        assert_eq!(scheme.colors[0x17], Color::from_hex("#17b840"));
+        assert_eq!(scheme.to_terminal_scheme().colors[9], scheme.colors[0x12]);
+
+        let emitted = scheme.to_yaml();
+        assert!(emitted.starts_with("system: \"base24\"\nname: \"One \\\"Dark\\\"\"\n"));
+        assert!(emitted.contains("\n  base0A: \"#0a5040\"\n"));
+        assert_eq!(Base16Scheme::parse(&emitted).unwrap(), scheme);
+
+        let truncated: String = yaml
+            .lines()
+            .take(5 + 20)
+            .map(|line| line.to_string() + "\n")
+            .collect();
+        let error = Base16Scheme::parse(&truncated).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<MissingSlots>().unwrap().0,
+            vec!["base14", "base15", "base16", "base17"]
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use once_cell::sync::Lazy;
+
+use crate::utils;
+use crate::Color;
+
+/// `utils::srgb_to_linear` of every 8 bit channel value
+static LINEAR_TABLE: Lazy<[f32; 256]> = Lazy::new(|| {
+    let mut table = [0.0f32; 256];
+    for (value, linear) in table.iter_mut().enumerate() {
+        *linear = utils::srgb_to_linear(value as f32 / 255.0f32);
+    }
+    table
+});
+
+/// rec. 709 luma weights out of 256, summing to 256 so white stays 255
+const LUMA_WEIGHTS: [u32; 3] = [54, 183, 19];
+
+fn check_lengths(input: usize, output: usize) {
+    assert_eq!(
+        input, output,
+        "Input has {} elements but output has {}",
+        input, output
+    );
+}
+
+/// converts every color into the matching slot of `output`, like calling
+/// `T::from` in a loop
+/// panics when the lengths differ
+pub fn convert_slice<T: From<Color>>(colors: &[Color], output: &mut [T]) {
+    check_lengths(colors.len(), output.len());
+    for (color, converted) in colors.iter().zip(output.iter_mut()) {
+        *converted = T::from(*color);
+    }
+}
+
+/// linear red, green and blue with alpha as is, all 0.0 - 1.0
+/// panics when the lengths differ
+pub fn srgb_to_linear_slice(colors: &[Color], output: &mut [[f32; 4]]) {
+    check_lengths(colors.len(), output.len());
+    let table = &*LINEAR_TABLE;
+    for (color, linear) in colors.iter().zip(output.iter_mut()) {
+        *linear = [
+            table[color.red as usize],
+            table[color.green as usize],
+            table[color.blue as usize],
+            color.alpha as f32 / 255.0f32,
+        ];
+    }
+}
+
+/// inverse of `srgb_to_linear_slice`, values outside 0.0 - 1.0 are clipped
+/// panics when the lengths differ
+pub fn linear_to_srgb_slice(linear: &[[f32; 4]], output: &mut [Color]) {
+    check_lengths(linear.len(), output.len());
+    let encode =
+        |value: f32| utils::unit_f32_to_u8(utils::linear_to_srgb(value.clamp(0.0f32, 1.0f32)));
+    for (channels, color) in linear.iter().zip(output.iter_mut()) {
+        *color = Color {
+            red: encode(channels[0]),
+            green: encode(channels[1]),
+            blue: encode(channels[2]),
+            alpha: utils::unit_f32_to_u8(channels[3]),
+        };
+    }
+}
+
+/// rec. 709 luma of the gamma encoded channels, alpha is ignored
+pub fn luma(red: u8, green: u8, blue: u8) -> u8 {
+    ((red as u32 * LUMA_WEIGHTS[0]
+        + green as u32 * LUMA_WEIGHTS[1]
+        + blue as u32 * LUMA_WEIGHTS[2]
+        + 128)
+        >> 8) as u8
+}
+
+/// `luma` of every color
+/// panics when the lengths differ
+pub fn to_grayscale_slice(colors: &[Color], output: &mut [u8]) {
+    check_lengths(colors.len(), output.len());
+    for (color, gray) in colors.iter().zip(output.iter_mut()) {
+        *gray = luma(color.red, color.green, color.blue);
+    }
+}
+
+/// `luma` of every pixel of a packed rgba buffer, four bytes a pixel
+/// uses avx2 when the cpu has it, with the same result as without
+/// panics unless `rgba` holds exactly `output.len()` pixels
+pub fn rgba_to_grayscale(rgba: &[u8], output: &mut [u8]) {
+    check_lengths(rgba.len(), output.len() * 4);
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // safety: avx2 support was just checked
+            unsafe { rgba_to_grayscale_avx2(rgba, output) };
+            return;
+        }
+    }
+    rgba_to_grayscale_scalar(rgba, output);
+}
+
+fn rgba_to_grayscale_scalar(rgba: &[u8], output: &mut [u8]) {
+    for (pixel, gray) in rgba.chunks_exact(4).zip(output.iter_mut()) {
+        *gray = luma(pixel[0], pixel[1], pixel[2]);
+    }
+}
+
+/// eight pixels at a time as 32 bit lanes, the remainder goes through the scalar path
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn rgba_to_grayscale_avx2(rgba: &[u8], output: &mut [u8]) {
+    use std::arch::x86_64::*;
+
+    let mask = _mm256_set1_epi32(0xff);
+    let red_weight = _mm256_set1_epi32(LUMA_WEIGHTS[0] as i32);
+    let green_weight = _mm256_set1_epi32(LUMA_WEIGHTS[1] as i32);
+    let blue_weight = _mm256_set1_epi32(LUMA_WEIGHTS[2] as i32);
+    let rounding = _mm256_set1_epi32(128);
+    let blocks = output.len() / 8;
+    let mut lanes = [0i32; 8];
+    for block in 0..blocks {
+        // loadu has no alignment requirement and the block is in bounds
+        let pixels = _mm256_loadu_si256(rgba.as_ptr().add(block * 32) as *const __m256i);
+        let red = _mm256_and_si256(pixels, mask);
+        let green = _mm256_and_si256(_mm256_srli_epi32::<8>(pixels), mask);
+        let blue = _mm256_and_si256(_mm256_srli_epi32::<16>(pixels), mask);
+        let sum = _mm256_add_epi32(
+            _mm256_add_epi32(
+                _mm256_mullo_epi32(red, red_weight),
+                _mm256_mullo_epi32(green, green_weight),
+            ),
+            _mm256_add_epi32(_mm256_mullo_epi32(blue, blue_weight), rounding),
+        );
+        let gray = _mm256_srli_epi32::<8>(sum);
+        _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, gray);
+        for (lane, value) in lanes.iter().enumerate() {
+            output[block * 8 + lane] = *value as u8;
+        }
+    }
+    rgba_to_grayscale_scalar(&rgba[blocks * 32..], &mut output[blocks * 8..]);
+}
+
+/// packs colors as red, green, blue, alpha bytes
+/// panics unless `output` has four bytes for every color
+pub fn colors_to_rgba(colors: &[Color], output: &mut [u8]) {
+    check_lengths(colors.len() * 4, output.len());
+    for (color, pixel) in colors.iter().zip(output.chunks_exact_mut(4)) {
+        pixel.copy_from_slice(&[color.red, color.green, color.blue, color.alpha]);
+    }
+}
+
+/// inverse of `colors_to_rgba`
+/// panics unless `rgba` has four bytes for every color
+pub fn rgba_to_colors(rgba: &[u8], output: &mut [Color]) {
+    check_lengths(rgba.len(), output.len() * 4);
+    for (pixel, color) in rgba.chunks_exact(4).zip(output.iter_mut()) {
+        *color = Color {
+            red: pixel[0],
+            green: pixel[1],
+            blue: pixel[2],
+            alpha: pixel[3],
+        };
+    }
+}
+
+mod tests {
+    /// pseudo random rgba bytes
+    #[allow(dead_code)]
+    fn noise(length: usize) -> Vec<u8> {
+        let mut random = crate::quantize::SplitMix64::new(7);
+        (0..length).map(|_| random.next_u64() as u8).collect()
+    }
+
+    #[test]
+    fn convert_tests() {
+        use super::*;
+        use crate::space::Hsl;
+        use crate::space::Oklab;
+        let rgba = noise(4 * 37);
+        let mut colors = vec![Color::new(); 37];
+        rgba_to_colors(&rgba, &mut colors);
+        let mut packed = vec![0u8; rgba.len()];
+        colors_to_rgba(&colors, &mut packed);
+        assert_eq!(packed, rgba);
+
+        let mut hsl = vec![Hsl::default(); colors.len()];
+        convert_slice(&colors, &mut hsl);
+        let mut oklab = vec![Oklab::default(); colors.len()];
+        convert_slice(&colors, &mut oklab);
+        for (index, color) in colors.iter().enumerate() {
+            assert_eq!(hsl[index], Hsl::from(*color));
+            assert_eq!(oklab[index], Oklab::from(*color));
+        }
+
+        let mut linear = vec![[0.0f32; 4]; colors.len()];
+        srgb_to_linear_slice(&colors, &mut linear);
+        for (color, channels) in colors.iter().zip(linear.iter()) {
+            assert_eq!(
+                channels[1].to_bits(),
+                utils::srgb_to_linear(color.green as f32 / 255.0f32).to_bits()
+            );
+            assert_eq!(channels[3], color.alpha as f32 / 255.0f32);
+        }
+        let mut back = vec![Color::new(); colors.len()];
+        linear_to_srgb_slice(&linear, &mut back);
+        assert_eq!(back, colors);
+    }
+
+    #[test]
+    fn grayscale_tests() {
+        use super::*;
+        assert_eq!(luma(255, 255, 255), 255);
+        assert_eq!(luma(0, 0, 0), 0);
+        assert_eq!(luma(255, 0, 0), 54);
+        assert_eq!(luma(0, 255, 0), 182);
+
+        // the dispatching path against the scalar one, with leftover pixels
+        for pixels in [0usize, 1, 7, 8, 9, 64, 1003].iter() {
+            let pixels = *pixels;
+            let rgba = noise(pixels * 4);
+            let mut expected = vec![0u8; pixels];
+            rgba_to_grayscale_scalar(&rgba, &mut expected);
+            let mut actual = vec![0u8; pixels];
+            rgba_to_grayscale(&rgba, &mut actual);
+            assert_eq!(actual, expected);
+
+            let mut colors = vec![Color::new(); pixels];
+            rgba_to_colors(&rgba, &mut colors);
+            let mut from_colors = vec![0u8; pixels];
+            to_grayscale_slice(&colors, &mut from_colors);
+            assert_eq!(from_colors, expected);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn length_tests() {
+        use super::*;
+        rgba_to_grayscale(&[0u8; 7], &mut [0u8; 2]);
+    }
+}
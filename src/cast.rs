@@ -0,0 +1,62 @@
+use std::error::Error;
+
+use bytemuck::PodCastError;
+
+use crate::Color;
+
+fn cast_error(error: PodCastError, length: usize) -> Box<dyn Error> {
+    match error {
+        PodCastError::SizeMismatch | PodCastError::OutputSliceWouldHaveSlop => {
+            format!("Buffer of {} bytes is not a whole number of colors", length).into()
+        }
+        PodCastError::TargetAlignmentGreaterAndInputNotAligned
+        | PodCastError::AlignmentMismatch => "Buffer is not aligned for colors".into(),
+    }
+}
+
+/// an rgba buffer as colors, without copying
+pub fn colors_from_bytes(bytes: &[u8]) -> Result<&[Color], Box<dyn Error>> {
+    bytemuck::try_cast_slice(bytes).map_err(|error| cast_error(error, bytes.len()))
+}
+
+pub fn colors_from_bytes_mut(bytes: &mut [u8]) -> Result<&mut [Color], Box<dyn Error>> {
+    let length = bytes.len();
+    bytemuck::try_cast_slice_mut(bytes).map_err(|error| cast_error(error, length))
+}
+
+/// colors as an rgba buffer, without copying
+pub fn colors_as_bytes(colors: &[Color]) -> &[u8] {
+    bytemuck::cast_slice(colors)
+}
+
+pub fn colors_as_bytes_mut(colors: &mut [Color]) -> &mut [u8] {
+    bytemuck::cast_slice_mut(colors)
+}
+
+mod tests {
+    #[test]
+    fn cast_tests() {
+        use super::*;
+        let mut bytes = vec![255u8, 136, 0, 255, 1, 2, 3, 4];
+        let colors = colors_from_bytes(&bytes).unwrap();
+        assert_eq!(colors, &[Color::rgb(255, 136, 0), Color::rgba(1, 2, 3, 4)]);
+        assert_eq!(colors.as_ptr() as usize, bytes.as_ptr() as usize);
+        assert_eq!(colors_as_bytes(colors), &bytes[..]);
+
+        colors_from_bytes_mut(&mut bytes).unwrap()[1].alpha = 255;
+        assert_eq!(bytes[7], 255);
+        let mut colors = vec![Color::new(); 2];
+        colors_as_bytes_mut(&mut colors)[4] = 9;
+        assert_eq!(colors[1].red, 9);
+
+        let error = colors_from_bytes(&bytes[..7]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Buffer of 7 bytes is not a whole number of colors"
+        );
+        assert!(colors_from_bytes_mut(&mut bytes[1..]).is_err());
+        assert!(colors_from_bytes(&[]).unwrap().is_empty());
+        // colors are byte aligned, so any offset that keeps whole colors works
+        assert_eq!(colors_from_bytes(&bytes[1..5]).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,444 @@
+use std::collections::HashSet;
+
+use crate::format::ColorFormat;
+use crate::palette::Palette;
+use crate::traits::*;
+use crate::Color;
+
+/// a language or config format `Palette::to_code` writes constants for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CodeTarget {
+    /// `--kebab-case` custom properties in a `:root` block
+    Css,
+    /// `$kebab-case` variables
+    Scss,
+    /// `@kebab-case` variables
+    Less,
+    /// a `tailwind.config.js` extending the theme colors
+    Tailwind,
+    /// `SCREAMING_SNAKE` consts of `colordata::Color`
+    Rust,
+    /// `SCREAMING_SNAKE` `0xrrggbbaa` defines and `{r, g, b, a}` initializers
+    C,
+    /// `camelCase` static `UIColor`s
+    Swift,
+    /// `camelCase` static swiftui `Color`s
+    SwiftUi,
+    /// `PascalCase` jetpack compose `Color(0xaarrggbb)` vals
+    Kotlin,
+    /// a `snake_case` `res/values/colors.xml`
+    AndroidXml,
+    /// `SCREAMING_SNAKE` `vec4` consts with channels 0.0 - 1.0
+    Glsl,
+}
+
+impl CodeTarget {
+    pub const ALL: [CodeTarget; 11] = [
+        CodeTarget::Css,
+        CodeTarget::Scss,
+        CodeTarget::Less,
+        CodeTarget::Tailwind,
+        CodeTarget::Rust,
+        CodeTarget::C,
+        CodeTarget::Swift,
+        CodeTarget::SwiftUi,
+        CodeTarget::Kotlin,
+        CodeTarget::AndroidXml,
+        CodeTarget::Glsl,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CodeTarget::Css => "css",
+            CodeTarget::Scss => "scss",
+            CodeTarget::Less => "less",
+            CodeTarget::Tailwind => "tailwind",
+            CodeTarget::Rust => "rust",
+            CodeTarget::C => "c",
+            CodeTarget::Swift => "swift",
+            CodeTarget::SwiftUi => "swiftui",
+            CodeTarget::Kotlin => "kotlin",
+            CodeTarget::AndroidXml => "android_xml",
+            CodeTarget::Glsl => "glsl",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<CodeTarget> {
+        CodeTarget::ALL
+            .iter()
+            .find(|target| target.name() == name)
+            .copied()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Case {
+    Kebab,
+    Snake,
+    ScreamingSnake,
+    Camel,
+    Pascal,
+}
+
+/// the ascii words of `name`, split at anything that is not a letter or digit
+/// and at case changes, so `darkRed`, `dark red` and `DARK_RED` agree and
+/// `HTMLRed` is `html red`
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for part in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let chars: Vec<char> = part.chars().collect();
+        let mut word = String::new();
+        for (index, c) in chars.iter().enumerate() {
+            let previous = index.checked_sub(1).map(|index| chars[index]);
+            let next = chars.get(index + 1);
+            let boundary = c.is_ascii_uppercase()
+                && match previous {
+                    Some(previous) if previous.is_ascii_uppercase() => {
+                        next.is_some_and(|next| next.is_ascii_lowercase())
+                    }
+                    Some(_) => true,
+                    None => false,
+                };
+            if boundary && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            word.push(c.to_ascii_lowercase());
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+    }
+    words
+}
+
+fn join(words: &[String], case: Case) -> String {
+    let capitalized = |word: &String| {
+        let mut chars = word.chars();
+        chars.next().map_or(String::new(), |first| {
+            first.to_ascii_uppercase().to_string() + chars.as_str()
+        })
+    };
+    match case {
+        Case::Kebab => words.join("-"),
+        Case::Snake => words.join("_"),
+        Case::ScreamingSnake => words.join("_").to_ascii_uppercase(),
+        Case::Camel => words
+            .iter()
+            .enumerate()
+            .map(|(index, word)| match index {
+                0 => word.clone(),
+                _ => capitalized(word),
+            })
+            .collect(),
+        Case::Pascal => words.iter().map(capitalized).collect(),
+    }
+}
+
+/// swift keywords that can come out of `Case::Camel`, as constant names they
+/// need backticks
+#[rustfmt::skip]
+const SWIFT_KEYWORDS: [&str; 53] = [
+    "associatedtype", "class", "deinit", "enum", "extension", "fileprivate", "func", "import",
+    "init", "inout", "internal", "let", "open", "operator", "private", "precedencegroup",
+    "protocol", "public", "rethrows", "static", "struct", "subscript", "typealias", "var", "break",
+    "case", "catch", "continue", "default", "defer", "do", "else", "fallthrough", "for", "guard",
+    "if", "in", "repeat", "return", "throw", "switch", "where", "while", "as", "await", "false",
+    "is", "nil", "self", "super", "throws", "true", "try",
+];
+
+/// `#rrggbb`, or `#rrggbbaa` when not opaque
+fn css_hex(color: &Color) -> String {
+    match color.alpha {
+        255 => color.format_string(ColorFormat::Hex),
+        _ => color.format_string(ColorFormat::Hex8),
+    }
+}
+
+/// the channels 0.0 - 1.0 to three digits, red first
+fn unit_channels(color: &Color) -> [String; 4] {
+    let (red, green, blue, alpha) = color.to_tuple_alpha();
+    [red, green, blue, alpha].map(|channel| format!("{:.3}", channel as f32 / 255.0f32))
+}
+
+impl Palette {
+    /// one identifier per entry in `case`, unnamed entries become `color 1`,
+    /// `color 2` and so on, names starting with a digit get a `color` word in
+    /// front and repeated names get a number after
+    fn identifiers(&self, case: Case) -> Vec<String> {
+        let mut used = HashSet::new();
+        let mut identifiers = Vec::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            let mut words = words(&entry.name);
+            if words.is_empty() {
+                words = vec!["color".to_string(), (index + 1).to_string()];
+            } else if words[0].starts_with(|c: char| c.is_ascii_digit()) {
+                words.insert(0, "color".to_string());
+            }
+            let mut identifier = join(&words, case);
+            let mut count = 1usize;
+            while !used.insert(identifier.clone()) {
+                count += 1;
+                let mut numbered = words.clone();
+                numbered.push(count.to_string());
+                identifier = join(&numbered, case);
+            }
+            identifiers.push(identifier);
+        }
+        identifiers
+    }
+
+    /// source code declaring a constant for every entry, named after it in the
+    /// target's usual case, alpha is kept everywhere
+    pub fn to_code(&self, target: CodeTarget) -> String {
+        let case = match target {
+            CodeTarget::Css | CodeTarget::Scss | CodeTarget::Less | CodeTarget::Tailwind => {
+                Case::Kebab
+            }
+            CodeTarget::AndroidXml => Case::Snake,
+            CodeTarget::Rust | CodeTarget::C | CodeTarget::Glsl => Case::ScreamingSnake,
+            CodeTarget::Swift | CodeTarget::SwiftUi => Case::Camel,
+            CodeTarget::Kotlin => Case::Pascal,
+        };
+        // kotlin keywords are all lower case so `Case::Pascal` never hits one
+        let identifiers: Vec<String> = self
+            .identifiers(case)
+            .into_iter()
+            .map(|identifier| match target {
+                CodeTarget::Swift | CodeTarget::SwiftUi
+                    if SWIFT_KEYWORDS.contains(&identifier.as_str()) =>
+                {
+                    format!("`{}`", identifier)
+                }
+                _ => identifier,
+            })
+            .collect();
+        let entries = identifiers
+            .iter()
+            .zip(self.entries.iter().map(|entry| &entry.color));
+        let (header, indent, footer): (&[&str], &str, &[&str]) = match target {
+            CodeTarget::Css => (&[":root {"], "  ", &["}"]),
+            CodeTarget::Tailwind => (
+                &[
+                    "module.exports = {",
+                    "  theme: {",
+                    "    extend: {",
+                    "      colors: {",
+                ],
+                "        ",
+                &["      },", "    },", "  },", "};"],
+            ),
+            CodeTarget::Rust => (&["use colordata::Color;", ""], "", &[]),
+            CodeTarget::Swift => (&["import UIKit", "", "extension UIColor {"], "    ", &["}"]),
+            CodeTarget::SwiftUi => (&["import SwiftUI", "", "extension Color {"], "    ", &["}"]),
+            CodeTarget::Kotlin => (&["import androidx.compose.ui.graphics.Color", ""], "", &[]),
+            CodeTarget::AndroidXml => (
+                &["<?xml version=\"1.0\" encoding=\"utf-8\"?>", "<resources>"],
+                "    ",
+                &["</resources>"],
+            ),
+            _ => (&[], "", &[]),
+        };
+        let mut lines: Vec<String> = header.iter().map(|line| line.to_string()).collect();
+        for (identifier, color) in entries {
+            let (red, green, blue, alpha) = color.to_tuple_alpha();
+            let [unit_red, unit_green, unit_blue, unit_alpha] = unit_channels(color);
+            let line = match target {
+                CodeTarget::Css => format!("--{}: {};", identifier, css_hex(color)),
+                CodeTarget::Scss => format!("${}: {};", identifier, css_hex(color)),
+                CodeTarget::Less => format!("@{}: {};", identifier, css_hex(color)),
+                CodeTarget::Tailwind => format!("'{}': '{}',", identifier, css_hex(color)),
+                CodeTarget::Rust => format!(
+                    "pub const {}: Color = Color::rgba({}, {}, {}, {});",
+                    identifier, red, green, blue, alpha
+                ),
+                CodeTarget::C => format!(
+                    "#define {}_RGBA 0x{:02x}{:02x}{:02x}{:02x}u\n#define {} {{ {}, {}, {}, {} }}",
+                    identifier, red, green, blue, alpha, identifier, red, green, blue, alpha
+                ),
+                CodeTarget::Swift => format!(
+                    "static let {} = UIColor(red: {}, green: {}, blue: {}, alpha: {})",
+                    identifier, unit_red, unit_green, unit_blue, unit_alpha
+                ),
+                CodeTarget::SwiftUi => format!(
+                    "static let {} = Color(red: {}, green: {}, blue: {}, opacity: {})",
+                    identifier, unit_red, unit_green, unit_blue, unit_alpha
+                ),
+                CodeTarget::Kotlin => format!(
+                    "val {} = Color(0x{:02X}{:02X}{:02X}{:02X})",
+                    identifier, alpha, red, green, blue
+                ),
+                CodeTarget::AndroidXml => {
+                    let value = match alpha {
+                        255 => format!("#{:02X}{:02X}{:02X}", red, green, blue),
+                        _ => format!("#{:02X}{:02X}{:02X}{:02X}", alpha, red, green, blue),
+                    };
+                    format!("<color name=\"{}\">{}</color>", identifier, value)
+                }
+                CodeTarget::Glsl => format!(
+                    "const vec4 {} = vec4({}, {}, {}, {});",
+                    identifier, unit_red, unit_green, unit_blue, unit_alpha
+                ),
+            };
+            for line in line.lines() {
+                lines.push(format!("{}{}", indent, line));
+            }
+        }
+        lines.extend(footer.iter().map(|line| line.to_string()));
+        lines.join("\n") + "\n"
+    }
+}
+
+mod tests {
+    /// the shared palette fixture plus names that are awkward as identifiers
+    #[allow(dead_code)]
+    fn awkward_names() -> crate::palette::Palette {
+        let mut palette = crate::palette::tests::sample();
+        palette.push("overlayHTMLTint", crate::Color::rgba(255, 136, 0, 128));
+        palette.push("100", crate::Color::rgb(0, 0, 0));
+        palette.push("scarlet", crate::Color::rgb(140, 0, 0));
+        palette
+    }
+
+    #[test]
+    fn identifier_tests() {
+        use super::*;
+        assert_eq!(words("darkRed"), ["dark", "red"]);
+        assert_eq!(words("DARK_RED"), ["dark", "red"]);
+        assert_eq!(words("HTMLRed 2"), ["html", "red", "2"]);
+        assert_eq!(words("gray50"), ["gray50"]);
+        assert_eq!(words("  -- "), Vec::<String>::new());
+
+        let palette = awkward_names();
+        assert_eq!(
+            palette.identifiers(Case::Kebab),
+            [
+                "scarlet",
+                "color-2",
+                "n-c-d",
+                "mint",
+                "sea",
+                "moss",
+                "white",
+                "overlay-html-tint",
+                "color-100",
+                "scarlet-2"
+            ]
+        );
+        assert_eq!(
+            palette.identifiers(Case::Camel),
+            [
+                "scarlet",
+                "color2",
+                "nCD",
+                "mint",
+                "sea",
+                "moss",
+                "white",
+                "overlayHtmlTint",
+                "color100",
+                "scarlet2"
+            ]
+        );
+        assert_eq!(
+            palette.identifiers(Case::Pascal)[7],
+            "OverlayHtmlTint".to_string()
+        );
+        assert_eq!(
+            palette.identifiers(Case::ScreamingSnake)[9],
+            "SCARLET_2".to_string()
+        );
+        for target in CodeTarget::ALL.iter() {
+            assert_eq!(CodeTarget::from_name(target.name()), Some(*target));
+        }
+        assert_eq!(CodeTarget::from_name("java"), None);
+    }
+
+    #[test]
+    fn code_tests() {
+        use super::*;
+        let mut palette = awkward_names();
+        palette
+            .entries
+            .retain(|entry| entry.name == "Scarlet" || entry.name == "overlayHTMLTint");
+        let code = |target: CodeTarget| palette.to_code(target);
+        assert_eq!(
+            code(CodeTarget::Css),
+            ":root {\n  --scarlet: #ff2400;\n  --overlay-html-tint: #ff880080;\n}\n"
+        );
+        assert_eq!(
+            code(CodeTarget::Scss),
+            "$scarlet: #ff2400;\n$overlay-html-tint: #ff880080;\n"
+        );
+        assert_eq!(
+            code(CodeTarget::Less),
+            "@scarlet: #ff2400;\n@overlay-html-tint: #ff880080;\n"
+        );
+        assert_eq!(
+            code(CodeTarget::Tailwind),
+            "module.exports = {\n  theme: {\n    extend: {\n      colors: {\n        \
+             'scarlet': '#ff2400',\n        'overlay-html-tint': '#ff880080',\n      \
+             },\n    },\n  },\n};\n"
+        );
+        assert_eq!(
+            code(CodeTarget::Rust),
+            "use colordata::Color;\n\n\
+             pub const SCARLET: Color = Color::rgba(255, 36, 0, 255);\n\
+             pub const OVERLAY_HTML_TINT: Color = Color::rgba(255, 136, 0, 128);\n"
+        );
+        assert_eq!(
+            code(CodeTarget::C),
+            "#define SCARLET_RGBA 0xff2400ffu\n#define SCARLET { 255, 36, 0, 255 }\n\
+             #define OVERLAY_HTML_TINT_RGBA 0xff880080u\n\
+             #define OVERLAY_HTML_TINT { 255, 136, 0, 128 }\n"
+        );
+        assert_eq!(
+            code(CodeTarget::Swift),
+            "import UIKit\n\nextension UIColor {\n    \
+             static let scarlet = UIColor(red: 1.000, green: 0.141, blue: 0.000, alpha: 1.000)\n    \
+             static let overlayHtmlTint = UIColor(red: 1.000, green: 0.533, blue: 0.000, alpha: 0.502)\n}\n"
+        );
+        assert!(code(CodeTarget::SwiftUi).contains(
+            "    static let scarlet = Color(red: 1.000, green: 0.141, blue: 0.000, opacity: 1.000)\n"
+        ));
+        assert_eq!(
+            code(CodeTarget::Kotlin),
+            "import androidx.compose.ui.graphics.Color\n\n\
+             val Scarlet = Color(0xFFFF2400)\nval OverlayHtmlTint = Color(0x80FF8800)\n"
+        );
+        assert_eq!(
+            code(CodeTarget::AndroidXml),
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n    \
+             <color name=\"scarlet\">#FF2400</color>\n    \
+             <color name=\"overlay_html_tint\">#80FF8800</color>\n</resources>\n"
+        );
+        assert_eq!(
+            code(CodeTarget::Glsl),
+            "const vec4 SCARLET = vec4(1.000, 0.141, 0.000, 1.000);\n\
+             const vec4 OVERLAY_HTML_TINT = vec4(1.000, 0.533, 0.000, 0.502);\n"
+        );
+        assert_eq!(
+            Palette::new("Empty").to_code(CodeTarget::Css),
+            ":root {\n}\n"
+        );
+
+        let mut reserved = Palette::new("Reserved");
+        for name in ["Default", "Self", "Class", "In", "Inner"].iter() {
+            reserved.push(name, Color::rgb(0, 0, 0));
+        }
+        let swift = reserved.to_code(CodeTarget::SwiftUi);
+        for name in ["`default`", "`self`", "`class`", "`in`", "inner"].iter() {
+            assert!(
+                swift.contains(&format!("    static let {} = Color(", name)),
+                "{}",
+                swift
+            );
+        }
+        assert!(reserved
+            .to_code(CodeTarget::Swift)
+            .contains("static let `default` = UIColor("));
+        let kotlin = reserved.to_code(CodeTarget::Kotlin);
+        for name in ["Default", "Self", "Class", "In", "Inner"].iter() {
+            assert!(kotlin.contains(&format!("\nval {} = Color(0xFF000000)\n", name)));
+        }
+    }
+}
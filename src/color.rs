@@ -1,13 +1,21 @@
+use std::error::Error;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::fmt::LowerHex;
+use std::fmt::UpperHex;
+use std::str::FromStr;
 
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::format;
+use crate::format::ColorFormat;
 use crate::traits::*;
 use crate::utils;
 
+/// laid out as four bytes in red, green, blue, alpha order
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[repr(C)]
 pub struct Color {
     pub red: u8,
     pub green: u8,
@@ -45,6 +53,12 @@ impl FromTuple for Color {
     }
 }
 
+// safety: four u8 fields under repr(C) leave no padding and every bit pattern is valid
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for Color {}
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for Color {}
+
 impl ComponentAsu8 for Color {}
 impl ComponentAsHexString for Color {}
 impl ComponentAsPercentage for Color {}
@@ -63,9 +77,39 @@ impl Default for Color {
     }
 }
 
+/// `{}` is `#rrggbb` and `{:#}` is `#rrggbbaa`, with a precision such as `{:.1}`
+/// it is `rgb(..%)`, or `rgba(..%)` with `{:#.1}`
 impl Display for Color {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.hex())
+        let format = match (f.precision(), f.alternate()) {
+            (None, false) => ColorFormat::Hex,
+            (None, true) => ColorFormat::Hex8,
+            (Some(_), false) => ColorFormat::RgbPercentage,
+            (Some(_), true) => ColorFormat::RgbaPercentage,
+        };
+        format::write_tuple(f, self.to_tuple_alpha(), format, f.precision())
+    }
+}
+
+/// `rrggbb`, or `rrggbbaa` with `{:#x}`
+impl LowerHex for Color {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02x}{:02x}{:02x}", self.red, self.green, self.blue)?;
+        if f.alternate() {
+            write!(f, "{:02x}", self.alpha)?;
+        }
+        Ok(())
+    }
+}
+
+/// `RRGGBB`, or `RRGGBBAA` with `{:#X}`
+impl UpperHex for Color {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02X}{:02X}{:02X}", self.red, self.green, self.blue)?;
+        if f.alternate() {
+            write!(f, "{:02X}", self.alpha)?;
+        }
+        Ok(())
     }
 }
 
@@ -77,11 +121,28 @@ impl From<&str> for Color {
             1 => Color::from_rgb(s),
             2 => Color::from_rgba(s),
             3 => Color::from_xrgba(s),
+            4 => Color::from_tuple_alpha(utils::hex_u32_to_tuple_alpha(s)),
             _ => panic!("Unrecognized color format: {}", s),
         }
     }
 }
 
+/// strict counterpart of `From<&str>`, the whole string has to be one color
+/// and bad input is an error instead of a panic
+impl FromStr for Color {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match utils::classify_strict(s)? {
+            0 => Ok(Color::from_hex8(s)),
+            1 => Ok(Color::from_rgb(s)),
+            2 => Ok(Color::from_rgba(s)),
+            3 => Ok(Color::from_xrgba(s)),
+            _ => Ok(Color::from_tuple_alpha(utils::hex_u32_to_tuple_alpha(s))),
+        }
+    }
+}
+
 impl From<&String> for Color {
     fn from(s: &String) -> Self {
         Color::from(s.as_str())
@@ -94,10 +155,296 @@ impl From<String> for Color {
     }
 }
 
+/// a `Color` out of a color string literal, checked at compile time
+///
+/// accepts what `Color::from_literal` does
+///
+/// ```
+/// use colordata::color;
+/// use colordata::Color;
+///
+/// const ORANGE: Color = color!("#ff8800");
+/// assert_eq!(ORANGE, Color::rgb(255, 136, 0));
+/// assert_eq!(color!("rgba(255, 136, 0, 0.5)"), Color::rgba(255, 136, 0, 127));
+/// ```
+///
+/// ```compile_fail
+/// let orange = colordata::color!("#ff880");
+/// ```
+#[macro_export]
+macro_rules! color {
+    ($literal:literal) => {{
+        const COLOR: $crate::Color = $crate::Color::from_literal($literal);
+        COLOR
+    }};
+}
+
+/// value of one ascii hex digit
+const fn hex_digit(byte: u8) -> u8 {
+    match byte {
+        b'0'..=b'9' => byte - b'0',
+        b'a'..=b'f' => byte - b'a' + 10,
+        b'A'..=b'F' => byte - b'A' + 10,
+        _ => panic!("Invalid hex digit in color literal"),
+    }
+}
+
+/// length of the whitespace character at `index`, 0 if there is none there,
+/// the same characters `\s` matches in the utils regexes
+const fn whitespace_len(bytes: &[u8], index: usize) -> usize {
+    match bytes.split_at(index).1 {
+        [b' ' | b'\t' | b'\n' | 0x0b | 0x0c | b'\r', ..] => 1,
+        [0xc2, 0x85 | 0xa0, ..] => 2,
+        [0xe1, 0x9a, 0x80, ..] | [0xe3, 0x80, 0x80, ..] | [0xe2, 0x81, 0x9f, ..] => 3,
+        [0xe2, 0x80, 0x80..=0x8a | 0xa8 | 0xa9 | 0xaf, ..] => 3,
+        _ => 0,
+    }
+}
+
+/// index of the first byte at or after `index` that does not start whitespace
+const fn skip_whitespace(bytes: &[u8], mut index: usize) -> usize {
+    while index < bytes.len() && whitespace_len(bytes, index) > 0 {
+        index += whitespace_len(bytes, index);
+    }
+    index
+}
+
+/// up to `limit` decimal digits at `index` as a number, how many there were
+/// and the index after them
+const fn parse_digits(bytes: &[u8], mut index: usize, limit: usize) -> (u64, usize, usize) {
+    let mut value = 0u64;
+    let mut digits = 0usize;
+    while index < bytes.len() && digits < limit && bytes[index].is_ascii_digit() {
+        value = value * 10 + (bytes[index] - b'0') as u64;
+        digits += 1;
+        index += 1;
+    }
+    (value, digits, index)
+}
+
+/// a channel at `index` as a fraction, the percent sign and the index after it,
+/// either up to 6 digits or up to 3 digits, a dot and up to 3 more
+const fn parse_channel(bytes: &[u8], index: usize) -> (u64, u64, bool, usize) {
+    let (whole, digits, mut index) = parse_digits(bytes, index, 6);
+    if digits == 0 {
+        panic!("Invalid number in color literal");
+    }
+    let mut numerator = whole;
+    let mut denominator = 1u64;
+    if index < bytes.len() && bytes[index] == b'.' {
+        let (fraction, fraction_digits, end) = parse_digits(bytes, index + 1, 3);
+        if digits > 3 || fraction_digits == 0 {
+            panic!("Invalid number in color literal");
+        }
+        denominator = 10u64.pow(fraction_digits as u32);
+        numerator = whole * denominator + fraction;
+        index = end;
+    }
+    let percent = index < bytes.len() && bytes[index] == b'%';
+    if percent {
+        index += 1;
+    }
+    (numerator, denominator, percent, index)
+}
+
+/// an alpha at `index` as a fraction and the index after it, one digit, a dot
+/// and at least one more, digits past the twelfth are read but ignored
+const fn parse_alpha(bytes: &[u8], index: usize) -> (u64, u64, usize) {
+    let (whole, digits, index) = parse_digits(bytes, index, 1);
+    if digits == 0 || index >= bytes.len() || bytes[index] != b'.' {
+        panic!("Invalid alpha in color literal");
+    }
+    let (fraction, fraction_digits, mut index) = parse_digits(bytes, index + 1, 12);
+    if fraction_digits == 0 {
+        panic!("Invalid alpha in color literal");
+    }
+    while index < bytes.len() && bytes[index].is_ascii_digit() {
+        index += 1;
+    }
+    let denominator = 10u64.pow(fraction_digits as u32);
+    (whole * denominator + fraction, denominator, index)
+}
+
+/// two hex digits at `index` as one byte
+const fn hex_byte(bytes: &[u8], index: usize) -> u8 {
+    hex_digit(bytes[index]) * 16 + hex_digit(bytes[index + 1])
+}
+
+/// a 0.0 - 1.0 fraction as 0 - 255, truncated like `utils::clamped_f32_to_u8`
+const fn fraction_to_u8(numerator: u64, denominator: u64) -> u8 {
+    (numerator * 255 / denominator) as u8
+}
+
+/// a percentage as 0 - 255, rounded to thousandths and then truncated like
+/// `utils::percentage_to_u8`
+const fn percentage_to_u8(numerator: u64, denominator: u64) -> u8 {
+    let thousandths = (numerator * 255 * 1000 * 2 + denominator * 100) / (denominator * 100 * 2);
+    (thousandths / 1000) as u8
+}
+
 impl Color {
     pub fn new() -> Self {
         Self::default()
     }
+
+    pub const fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Color::rgba(red, green, blue, 255)
+    }
+
+    pub const fn rgba(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+        Color {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+
+    /// an opaque color out of `0xRRGGBB`
+    /// panics if `hex` is above `0xffffff`, which is a build error in constants
+    pub const fn from_hex_u32(hex: u32) -> Self {
+        assert!(hex <= 0xffffff, "Hex color out of range");
+        Color::rgb((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+    }
+
+    /// exactly the strings `from_str` accepts, `#rgb`, `#rgba`, `#rrggbb`,
+    /// `#rrggbbaa`, `rgb(r, g, b)`, `rgba(r, g, b, a)`, `rr/gg/bb/aa`, `0xRRGGBB`
+    /// and `0xAARRGGBB`, converted exactly as `from_str` does
+    /// usable in constants, panics on anything else, which fails the build there
+    pub const fn from_literal(s: &str) -> Self {
+        let bytes = s.as_bytes();
+        match bytes {
+            [b'#', ..] => {
+                let width = match bytes.len() {
+                    4 | 5 => 1,
+                    7 | 9 => 2,
+                    _ => panic!("Invalid hex color literal length"),
+                };
+                let mut channels = [255u8; 4];
+                let mut index = 0;
+                while index < (bytes.len() - 1) / width {
+                    let start = 1 + index * width;
+                    channels[index] = match width {
+                        1 => hex_digit(bytes[start]) * 17,
+                        _ => hex_byte(bytes, start),
+                    };
+                    index += 1;
+                }
+                return Color::rgba(channels[0], channels[1], channels[2], channels[3]);
+            }
+            [b'0', b'x' | b'X', ..] => {
+                let alpha = match bytes.len() {
+                    8 => 255,
+                    10 => hex_byte(bytes, 2),
+                    _ => panic!("Invalid 0x color literal length"),
+                };
+                let start = bytes.len() - 6;
+                return Color::rgba(
+                    hex_byte(bytes, start),
+                    hex_byte(bytes, start + 2),
+                    hex_byte(bytes, start + 4),
+                    alpha,
+                );
+            }
+            [_, _, b'/', _, _, b'/', _, _, b'/', _, _] => {
+                return Color::rgba(
+                    hex_byte(bytes, 0),
+                    hex_byte(bytes, 3),
+                    hex_byte(bytes, 6),
+                    hex_byte(bytes, 9),
+                );
+            }
+            _ => {}
+        }
+        let (count, mut index) = match bytes {
+            [b'r' | b'R', b'g' | b'G', b'b' | b'B', b'(', ..] => (3, 4),
+            [b'r' | b'R', b'g' | b'G', b'b' | b'B', b'a' | b'A', b'(', ..] => (4, 5),
+            _ => panic!("Unrecognized color literal"),
+        };
+        let mut channels = [255u8; 4];
+        let mut percentages = 0;
+        let mut channel = 0;
+        while channel < count {
+            if channel > 0 {
+                if index >= bytes.len() || bytes[index] != b',' {
+                    panic!("Malformed rgb color literal");
+                }
+                index = skip_whitespace(bytes, index + 1);
+            }
+            if channel == 3 {
+                let (numerator, denominator, end) = parse_alpha(bytes, index);
+                if numerator > denominator {
+                    panic!("Alpha out of range in color literal");
+                }
+                channels[3] = fraction_to_u8(numerator, denominator);
+                index = end;
+            } else {
+                let (numerator, denominator, percent, end) = parse_channel(bytes, index);
+                channels[channel] = if percent {
+                    if numerator > denominator * 100 {
+                        panic!("Percentage out of range in color literal");
+                    }
+                    percentages += 1;
+                    percentage_to_u8(numerator, denominator)
+                } else {
+                    if denominator != 1 || numerator > 255 {
+                        panic!("Channel out of range in color literal");
+                    }
+                    numerator as u8
+                };
+                index = end;
+            }
+            channel += 1;
+        }
+        if percentages != 0 && percentages != 3 {
+            panic!("Mixed percentage and plain channels in color literal");
+        }
+        if index + 1 != bytes.len() || bytes[index] != b')' {
+            panic!("Malformed rgb color literal");
+        }
+        Color::rgba(channels[0], channels[1], channels[2], channels[3])
+    }
+
+    /// blends `self` over `background` using source-over compositing,
+    /// the result is opaque whenever `background` is
+    pub fn composite_over(&self, background: Color) -> Color {
+        let fg_alpha = self.alpha as f32 / 255.0f32;
+        let bg_alpha = background.alpha as f32 / 255.0f32;
+        let alpha = fg_alpha + bg_alpha * (1.0f32 - fg_alpha);
+        if alpha <= 0.0f32 {
+            return Color::from_tuple_alpha((0, 0, 0, 0));
+        }
+        let blend = |fg: u8, bg: u8| {
+            let fg = fg as f32 / 255.0f32;
+            let bg = bg as f32 / 255.0f32;
+            utils::unit_f32_to_u8((fg * fg_alpha + bg * bg_alpha * (1.0f32 - fg_alpha)) / alpha)
+        };
+        Color {
+            red: blend(self.red, background.red),
+            green: blend(self.green, background.green),
+            blue: blend(self.blue, background.blue),
+            alpha: utils::unit_f32_to_u8(alpha),
+        }
+    }
+
+    /// mixes `self` with white, `amount` is 0.0 - 1.0 and alpha is kept
+    pub fn lighten(&self, amount: f32) -> Color {
+        let amount = amount.clamp(0.0f32, 1.0f32);
+        let mix =
+            |channel: u8| (channel as f32 + (255.0f32 - channel as f32) * amount).round() as u8;
+        Color::from_tuple_alpha((mix(self.red), mix(self.green), mix(self.blue), self.alpha))
+    }
+
+    /// mixes `self` with black, `amount` is 0.0 - 1.0 and alpha is kept
+    pub fn darken(&self, amount: f32) -> Color {
+        let amount = amount.clamp(0.0f32, 1.0f32);
+        let mix = |channel: u8| (channel as f32 * (1.0f32 - amount)).round() as u8;
+        Color::from_tuple_alpha((mix(self.red), mix(self.green), mix(self.blue), self.alpha))
+    }
+
+    pub fn with_alpha(&self, alpha: u8) -> Color {
+        Color { alpha, ..*self }
+    }
 }
 
 mod tests {
@@ -202,4 +549,179 @@ mod tests {
         assert_eq!(color.blue, 17);
         assert_eq!(color.alpha, 255);
     }
+    #[test]
+    fn from_str_tests() {
+        use super::*;
+        let yellow = Color::from_hex("#ffcc11");
+        assert_eq!("#ffcc11".parse::<Color>().unwrap(), yellow);
+        assert_eq!("#fc1".parse::<Color>().unwrap(), yellow);
+        assert_eq!("rgb(255, 204, 17)".parse::<Color>().unwrap(), yellow);
+        assert_eq!("rgba(255, 204, 17, 1.0)".parse::<Color>().unwrap(), yellow);
+        assert_eq!("ff/cc/11/ff".parse::<Color>().unwrap(), yellow);
+        assert_eq!("0xffcc11".parse::<Color>().unwrap(), yellow);
+        assert_eq!(
+            "0X80FFCC11".parse::<Color>().unwrap(),
+            yellow.with_alpha(0x80)
+        );
+        assert_eq!(Color::from("0x80ffcc11"), yellow.with_alpha(0x80));
+        assert_eq!(Color::from("color: 0xffcc11;"), yellow);
+
+        for bad in [
+            "",
+            "#ffcc1",
+            "#ffcc11 trailing",
+            "color: #ffcc11",
+            "rgb(300, 0, 0)",
+            "rgb(1.5, 0, 0)",
+            "rgb(101%, 0%, 0%)",
+            "rgb(50%, 0, 0)",
+            "rgba(50%,0,0,0.5)",
+            "rgba(1,2,3,5.0)",
+            "yellow",
+            "0xffcc1",
+            "0xffcc11ff0",
+            "ffcc11",
+        ]
+        .iter()
+        {
+            assert!(bad.parse::<Color>().is_err(), "{}", bad);
+        }
+    }
+    #[test]
+    fn mix_tests() {
+        use super::*;
+        let color = Color::from_hex8("#80402080");
+        assert_eq!(color.lighten(0.5), Color::from_hex8("#c0a09080"));
+        assert_eq!(color.darken(0.5), Color::from_hex8("#40201080"));
+        assert_eq!(color.lighten(1.0), Color::from_hex8("#ffffff80"));
+        assert_eq!(color.darken(2.0), Color::from_hex8("#00000080"));
+        assert_eq!(color.with_alpha(255), Color::from_hex("#804020"));
+    }
+    #[test]
+    fn const_tests() {
+        use super::*;
+        const ORANGE: Color = Color::rgb(255, 136, 0);
+        const HEX: Color = Color::from_hex_u32(0xff8800);
+        assert_eq!(ORANGE, Color::from_hex("#ff8800"));
+        assert_eq!(HEX, ORANGE);
+        assert_eq!(Color::from_hex_u32(0xffffff), Color::rgb(255, 255, 255));
+        assert!(std::panic::catch_unwind(|| Color::from_hex_u32(0x1000000)).is_err());
+        assert_eq!(Color::rgba(1, 2, 3, 4), Color::from_hex8("#01020304"));
+        assert_eq!(crate::color!("#ff8800"), ORANGE);
+        assert_eq!(crate::color!("#F80"), ORANGE);
+        assert_eq!(crate::color!("#f808"), ORANGE.with_alpha(0x88));
+        assert_eq!(crate::color!("#ff880080"), ORANGE.with_alpha(0x80));
+        assert_eq!(crate::color!("rgb(255,136,0)"), ORANGE);
+        assert_eq!(crate::color!("RGB(100%,\t53.334%, 0%)"), ORANGE);
+        // the runtime parsers truncate, so these would be off by one if rounded
+        assert_eq!(
+            crate::color!("rgba(255, 136, 0, 0.5)"),
+            Color::from_rgba("rgba(255, 136, 0, 0.5)")
+        );
+        assert_eq!(
+            crate::color!("rgb(100%, 53.333%, 0%)"),
+            Color::from_rgb("rgb(100%, 53.333%, 0%)")
+        );
+        assert_eq!(
+            crate::color!("rgba(50%, 0.2%, 99.9%, 0.33334)"),
+            Color::from_rgba("rgba(50%, 0.2%, 99.9%, 0.33334)")
+        );
+        for step in 0..=1000 {
+            let alpha = format!("rgba(0, 0, 0, {}.{:03})", step / 1000, step % 1000);
+            assert_eq!(
+                Color::from_literal(&alpha),
+                Color::from_rgba(&alpha),
+                "{}",
+                alpha
+            );
+            let percentage = format!("rgb({}.{}%, 0%, 0%)", step / 10, step % 10);
+            assert_eq!(
+                Color::from_literal(&percentage),
+                Color::from_rgb(&percentage),
+                "{}",
+                percentage
+            );
+        }
+        assert_eq!(crate::color!("rgba(255, 136, 0, 1.0)"), ORANGE);
+        assert_eq!(crate::color!("ff/88/00/80"), ORANGE.with_alpha(0x80));
+        assert_eq!(crate::color!("0XFF8800"), ORANGE);
+        assert_eq!(crate::color!("0x80ff8800"), ORANGE.with_alpha(0x80));
+        for good in [
+            "#abc",
+            "rgb(000255,0,1)",
+            "Rgb(12.5%,\u{a0}0.001%, 100.000%)",
+            "rgba(1, 2, 3, 0.1234567890123456)",
+            "rgbA(0,0,0,\n0.5)",
+            "AB/cd/EF/01",
+            "0x01020304",
+        ]
+        .iter()
+        {
+            assert_eq!(
+                Color::from_literal(good),
+                good.parse::<Color>().unwrap(),
+                "{}",
+                good
+            );
+        }
+        for bad in [
+            "",
+            "#ff888",
+            "#ff880g",
+            "rgb(256, 0, 0)",
+            "rgb(1.5, 0, 0)",
+            "rgb(101%, 0, 0)",
+            "rgb(0, 0)",
+            "rgb(0, 0, 0",
+            "rgb(0, 0, 0) ",
+            "rgba(0, 0, 0, 1.5)",
+            "rgb(0, 0, .)",
+            "hsl(0, 0%, 0%)",
+            "rgba(255, 136, 0, 1)",
+            "rgba(255, 136, 0, .5)",
+            "rgba(255, 136, 0, 50%)",
+            "rgb(255 , 136, 0)",
+            "rgb( 255, 136, 0)",
+            "rgb(255, 136, 0 )",
+            "rgb(50%, 0, 0)",
+            "rgb(1234.5%, 0%, 0%)",
+            "rgb(1.2345%, 0%, 0%)",
+            "rgb(1234567, 0, 0)",
+            "0xff880",
+            "ff/88/00",
+            "ff/88/00/8g",
+        ]
+        .iter()
+        {
+            assert!(
+                std::panic::catch_unwind(|| Color::from_literal(bad)).is_err(),
+                "{}",
+                bad
+            );
+            assert!(bad.parse::<Color>().is_err(), "{}", bad);
+        }
+    }
+    #[test]
+    fn layout_tests() {
+        use super::*;
+        assert_eq!(std::mem::size_of::<Color>(), 4);
+        assert_eq!(std::mem::align_of::<Color>(), 1);
+        let colors = [Color::rgba(1, 2, 3, 4)];
+        // safety: size checked above and repr(C) fixes the field order
+        let bytes: [u8; 4] = unsafe { std::mem::transmute(colors) };
+        assert_eq!(bytes, [1, 2, 3, 4]);
+    }
+    #[test]
+    fn display_tests() {
+        use super::*;
+        let mint = Color::from_hex8("#abf78880");
+        assert_eq!(format!("{}", mint), "#abf788");
+        assert_eq!(format!("{:#}", mint), "#abf78880");
+        assert_eq!(format!("{:x}", mint), "abf788");
+        assert_eq!(format!("{:#x}", mint), "abf78880");
+        assert_eq!(format!("{:X}", mint), "ABF788");
+        assert_eq!(format!("{:#X}", mint), "ABF78880");
+        assert_eq!(format!("{:.1}", mint), "rgb(67.1%,96.9%,53.3%)");
+        assert_eq!(format!("{:#.2}", mint), "rgba(67.06%,96.86%,53.33%,0.50)");
+    }
 }
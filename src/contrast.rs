@@ -0,0 +1,433 @@
+use crate::space::Oklch;
+use crate::traits::*;
+use crate::utils;
+use crate::Color;
+
+/// conformance level of the wcag 2.x contrast success criteria
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WcagLevel {
+    AA,
+    AAA,
+}
+
+/// what the foreground color is used for
+/// large text is at least 18pt, or 14pt bold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WcagContent {
+    NormalText,
+    LargeText,
+    NonText,
+}
+
+/// outcome of a wcag contrast check
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WcagReport {
+    pub level: WcagLevel,
+    pub content: WcagContent,
+    pub ratio: f32,
+    pub required: f32,
+    pub passes: bool,
+}
+
+impl WcagLevel {
+    /// minimum contrast ratio for the given content
+    /// (SC 1.4.3, 1.4.6 and 1.4.11, the latter has no AAA variant)
+    pub fn required_ratio(&self, content: WcagContent) -> f32 {
+        match (self, content) {
+            (WcagLevel::AA, WcagContent::NormalText) => 4.5f32,
+            (WcagLevel::AA, WcagContent::LargeText) => 3.0f32,
+            (WcagLevel::AAA, WcagContent::NormalText) => 7.0f32,
+            (WcagLevel::AAA, WcagContent::LargeText) => 4.5f32,
+            (_, WcagContent::NonText) => 3.0f32,
+        }
+    }
+
+    pub fn check(&self, foreground: Color, background: Color, content: WcagContent) -> WcagReport {
+        let ratio = contrast_ratio(foreground, background);
+        let required = self.required_ratio(content);
+        WcagReport {
+            level: *self,
+            content,
+            ratio,
+            required,
+            passes: ratio >= required,
+        }
+    }
+}
+
+impl Color {
+    /// wcag 2.x relative luminance, 0.0 for black and 1.0 for white
+    /// alpha is ignored
+    pub fn relative_luminance(&self) -> f32 {
+        let red = utils::srgb_to_linear(self.red as f32 / 255.0f32);
+        let green = utils::srgb_to_linear(self.green as f32 / 255.0f32);
+        let blue = utils::srgb_to_linear(self.blue as f32 / 255.0f32);
+        0.2126f32 * red + 0.7152f32 * green + 0.0722f32 * blue
+    }
+}
+
+/// wcag 2.x contrast ratio between 1.0 and 21.0
+/// `foreground` is composited over `background` first, the alpha of
+/// `background` itself is ignored
+pub fn contrast_ratio(foreground: Color, background: Color) -> f32 {
+    let background = Color::from_tuple(background.to_tuple());
+    let foreground = foreground.composite_over(background);
+    let first = foreground.relative_luminance();
+    let second = background.relative_luminance();
+    let (lighter, darker) = match first > second {
+        true => (first, second),
+        false => (second, first),
+    };
+    (lighter + 0.05f32) / (darker + 0.05f32)
+}
+
+// APCA-W3 0.0.98G-4g constants
+const APCA_MAIN_TRC: f32 = 2.4f32;
+const APCA_NORM_BG: f32 = 0.56f32;
+const APCA_NORM_TXT: f32 = 0.57f32;
+const APCA_REV_TXT: f32 = 0.62f32;
+const APCA_REV_BG: f32 = 0.65f32;
+const APCA_BLK_THRS: f32 = 0.022f32;
+const APCA_BLK_CLMP: f32 = 1.414f32;
+const APCA_SCALE: f32 = 1.14f32;
+const APCA_LO_OFFSET: f32 = 0.027f32;
+const APCA_LO_CLIP: f32 = 0.1f32;
+const APCA_DELTA_Y_MIN: f32 = 0.0005f32;
+
+/// minimum font size in px per weight (100 to 900) for each 5 Lc step
+/// 999 means prohibited, 777 means non-text elements only
+#[rustfmt::skip]
+const APCA_FONT_LOOKUP: [(f32, [f32; 9]); 25] = [
+    (0.0, [999.0, 999.0, 999.0, 999.0, 999.0, 999.0, 999.0, 999.0, 999.0]),
+    (10.0, [999.0, 999.0, 999.0, 999.0, 999.0, 999.0, 999.0, 999.0, 999.0]),
+    (15.0, [777.0, 777.0, 777.0, 777.0, 777.0, 777.0, 777.0, 777.0, 777.0]),
+    (20.0, [777.0, 777.0, 777.0, 777.0, 777.0, 777.0, 777.0, 777.0, 777.0]),
+    (25.0, [777.0, 777.0, 777.0, 120.0, 120.0, 108.0, 96.0, 96.0, 96.0]),
+    (30.0, [777.0, 777.0, 120.0, 108.0, 108.0, 96.0, 72.0, 72.0, 72.0]),
+    (35.0, [777.0, 120.0, 108.0, 96.0, 72.0, 60.0, 48.0, 48.0, 48.0]),
+    (40.0, [120.0, 108.0, 96.0, 60.0, 48.0, 42.0, 32.0, 32.0, 32.0]),
+    (45.0, [108.0, 96.0, 72.0, 42.0, 32.0, 28.0, 24.0, 24.0, 24.0]),
+    (50.0, [96.0, 72.0, 60.0, 32.0, 28.0, 24.0, 21.0, 21.0, 21.0]),
+    (55.0, [80.0, 60.0, 48.0, 28.0, 24.0, 21.0, 18.0, 18.0, 18.0]),
+    (60.0, [72.0, 48.0, 42.0, 24.0, 21.0, 18.0, 16.0, 16.0, 18.0]),
+    (65.0, [68.0, 46.0, 32.0, 21.75, 19.0, 17.0, 15.0, 16.0, 18.0]),
+    (70.0, [64.0, 44.0, 28.0, 19.5, 18.0, 16.0, 14.5, 16.0, 18.0]),
+    (75.0, [60.0, 42.0, 24.0, 18.0, 16.0, 15.0, 14.0, 16.0, 18.0]),
+    (80.0, [56.0, 38.25, 23.0, 17.25, 15.81, 14.0, 13.0, 16.0, 18.0]),
+    (85.0, [52.0, 34.5, 22.0, 16.5, 15.625, 13.5, 13.0, 16.0, 18.0]),
+    (90.0, [48.0, 32.0, 21.0, 16.0, 15.5, 13.0, 12.0, 16.0, 18.0]),
+    (95.0, [45.0, 28.0, 19.5, 15.5, 15.0, 12.0, 11.0, 16.0, 18.0]),
+    (100.0, [42.0, 26.5, 18.5, 15.0, 14.5, 11.5, 10.0, 16.0, 18.0]),
+    (105.0, [39.0, 25.0, 18.0, 14.0, 14.0, 11.0, 10.0, 16.0, 18.0]),
+    (110.0, [36.0, 24.0, 18.0, 14.0, 13.0, 10.0, 9.0, 16.0, 18.0]),
+    (115.0, [34.5, 22.5, 17.25, 12.5, 11.875, 9.375, 8.5, 16.0, 18.0]),
+    (120.0, [33.0, 21.0, 16.5, 11.0, 11.0, 9.0, 8.0, 16.0, 18.0]),
+    (125.0, [32.0, 21.0, 16.5, 10.0, 10.0, 8.0, 8.0, 16.0, 18.0]),
+];
+
+/// apca screen luminance, a plain 2.4 power curve instead of the srgb piecewise one
+fn apca_luminance(color: Color) -> f32 {
+    let channel = |c: u8| (c as f32 / 255.0f32).powf(APCA_MAIN_TRC);
+    0.2126729f32 * channel(color.red)
+        + 0.7151522f32 * channel(color.green)
+        + 0.0721750f32 * channel(color.blue)
+}
+
+/// soft clamps near black luminance
+fn apca_soft_clamp(y: f32) -> f32 {
+    match y > APCA_BLK_THRS {
+        true => y,
+        false => y + (APCA_BLK_THRS - y).powf(APCA_BLK_CLMP),
+    }
+}
+
+/// apca-w3 lightness contrast (Lc) of `text` over `background`, roughly -108 to 106
+/// positive for dark text on a light background, negative for light text on a dark one
+/// `text` is composited over `background` first, the alpha of `background` is ignored
+pub fn apca_contrast(text: Color, background: Color) -> f32 {
+    let background = Color::from_tuple(background.to_tuple());
+    let text = text.composite_over(background);
+    let text_y = apca_soft_clamp(apca_luminance(text));
+    let background_y = apca_soft_clamp(apca_luminance(background));
+
+    if (background_y - text_y).abs() < APCA_DELTA_Y_MIN {
+        return 0.0f32;
+    }
+
+    let output = match background_y > text_y {
+        true => {
+            let sapc = (background_y.powf(APCA_NORM_BG) - text_y.powf(APCA_NORM_TXT)) * APCA_SCALE;
+            match sapc < APCA_LO_CLIP {
+                true => 0.0f32,
+                false => sapc - APCA_LO_OFFSET,
+            }
+        }
+        false => {
+            let sapc = (background_y.powf(APCA_REV_BG) - text_y.powf(APCA_REV_TXT)) * APCA_SCALE;
+            match sapc > -APCA_LO_CLIP {
+                true => 0.0f32,
+                false => sapc + APCA_LO_OFFSET,
+            }
+        }
+    };
+    output * 100.0f32
+}
+
+/// smallest font size in px that apca allows for `text` over `background`
+/// at `weight` (100 to 900, rounded to the nearest hundred)
+/// `None` when the pair is not usable for text at that weight
+/// the Lc value is rounded down to the lookup table's 5 Lc steps
+pub fn apca_min_font_size(text: Color, background: Color, weight: u16) -> Option<f32> {
+    let lc = apca_contrast(text, background).abs();
+    let column = ((weight.clamp(100, 900) as f32 / 100.0f32).round() as usize) - 1;
+    let size = APCA_FONT_LOOKUP
+        .iter()
+        .rev()
+        .find(|(step, _)| lc >= *step)
+        .map(|(_, sizes)| sizes[column])
+        .unwrap_or(999f32);
+    match size >= 777f32 {
+        true => None,
+        false => Some(size),
+    }
+}
+
+/// which contrast algorithm a target refers to
+/// wcag targets are ratios (4.5, 7.0, ...), apca targets are absolute Lc values (60, 75, ...)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContrastMetric {
+    Wcag,
+    Apca,
+}
+
+impl ContrastMetric {
+    /// contrast of `foreground` over `background`, apca polarity is dropped
+    pub fn measure(&self, foreground: Color, background: Color) -> f32 {
+        match self {
+            ContrastMetric::Wcag => contrast_ratio(foreground, background),
+            ContrastMetric::Apca => apca_contrast(foreground, background).abs(),
+        }
+    }
+}
+
+impl Color {
+    /// the color closest in oklch lightness to `self` that reaches `target`
+    /// against `background`, hue is kept and chroma only reduced where srgb
+    /// can not represent it
+    /// `None` if neither darkening nor lightening can reach `target`
+    pub fn ensure_contrast(
+        &self,
+        background: Color,
+        target: f32,
+        metric: ContrastMetric,
+    ) -> Option<Color> {
+        if metric.measure(*self, background) >= target {
+            return Some(*self);
+        }
+        let lch = Oklch::from(*self);
+        let alpha = self.alpha;
+        let with_lightness = |l: f32| {
+            let mut color = Color::from(Oklch::new(l, lch.c, lch.h).clamp_chroma());
+            color.alpha = alpha;
+            color
+        };
+        let passes = |l: f32| metric.measure(with_lightness(l), background) >= target;
+
+        let mut best: Option<(f32, Color)> = None;
+        for end in [0.0f32, 1.0f32].iter() {
+            let steps = 100;
+            let mut failing = lch.l;
+            for step in 1..=steps {
+                let l = lch.l + (end - lch.l) * step as f32 / steps as f32;
+                if !passes(l) {
+                    failing = l;
+                    continue;
+                }
+                let mut passing = l;
+                for _ in 0..16 {
+                    let middle = (failing + passing) / 2.0f32;
+                    match passes(middle) {
+                        true => passing = middle,
+                        false => failing = middle,
+                    }
+                }
+                let distance = (passing - lch.l).abs();
+                if best.is_none_or(|(best_distance, _)| distance < best_distance) {
+                    best = Some((distance, with_lightness(passing)));
+                }
+                break;
+            }
+        }
+        best.map(|(_, color)| color)
+    }
+}
+
+/// the candidate with the highest contrast against `background`
+pub fn best_text_color(
+    background: Color,
+    candidates: &[Color],
+    metric: ContrastMetric,
+) -> Option<Color> {
+    candidates.iter().copied().max_by(|first, second| {
+        metric
+            .measure(*first, background)
+            .partial_cmp(&metric.measure(*second, background))
+            .unwrap()
+    })
+}
+
+mod tests {
+    #[test]
+    fn luminance_tests() {
+        use super::*;
+        assert!(Color::from_hex("#000000").relative_luminance().abs() < f32::EPSILON);
+        assert!((Color::from_hex("#ffffff").relative_luminance() - 1.0f32).abs() < 1e-6);
+        assert!((Color::from_hex("#ff0000").relative_luminance() - 0.2126f32).abs() < 1e-6);
+    }
+    #[test]
+    fn contrast_ratio_tests() {
+        use super::*;
+        let black = Color::from_hex("#000000");
+        let white = Color::from_hex("#ffffff");
+        assert!((contrast_ratio(black, white) - 21.0f32).abs() < 1e-4);
+        assert!((contrast_ratio(white, black) - 21.0f32).abs() < 1e-4);
+        assert!((contrast_ratio(white, white) - 1.0f32).abs() < f32::EPSILON);
+        let gray = Color::from_hex("#767676");
+        assert!((contrast_ratio(gray, white) - 4.54f32).abs() < 0.01);
+
+        // half transparent black over white is #808080
+        let translucent = Color::from_hex8("#00000080");
+        let mid_gray = Color::from_hex("#7f7f7f");
+        assert!(
+            (contrast_ratio(translucent, white) - contrast_ratio(mid_gray, white)).abs() < 0.05
+        );
+    }
+    #[test]
+    fn wcag_level_tests() {
+        use super::*;
+        let white = Color::from_hex("#ffffff");
+        let gray = Color::from_hex("#767676");
+        let report = WcagLevel::AA.check(gray, white, WcagContent::NormalText);
+        assert!(report.passes);
+        assert!((report.required - 4.5f32).abs() < f32::EPSILON);
+        assert!(
+            !WcagLevel::AAA
+                .check(gray, white, WcagContent::NormalText)
+                .passes
+        );
+        assert!(
+            WcagLevel::AAA
+                .check(gray, white, WcagContent::LargeText)
+                .passes
+        );
+
+        let light_gray = Color::from_hex("#949494");
+        assert!(
+            !WcagLevel::AA
+                .check(light_gray, white, WcagContent::NormalText)
+                .passes
+        );
+        assert!(
+            WcagLevel::AA
+                .check(light_gray, white, WcagContent::NonText)
+                .passes
+        );
+    }
+    #[test]
+    fn apca_tests() {
+        use super::*;
+        // reference values from the apca-w3 test suite
+        let cases = [
+            ("#888888", "#ffffff", 63.056f32),
+            ("#ffffff", "#888888", -68.541f32),
+            ("#000000", "#aaaaaa", 58.146f32),
+            ("#aaaaaa", "#000000", -56.241f32),
+            ("#112233", "#ddeeff", 91.668f32),
+            ("#ddeeff", "#112233", -93.068f32),
+        ];
+        for (text, background, expected) in cases.iter() {
+            let lc = apca_contrast(Color::from_hex(text), Color::from_hex(background));
+            assert!(
+                (lc - expected).abs() < 0.01,
+                "{} on {}: {}",
+                text,
+                background,
+                lc
+            );
+        }
+        let gray = Color::from_hex("#777777");
+        assert!(apca_contrast(gray, gray).abs() < f32::EPSILON);
+    }
+    #[test]
+    fn apca_font_size_tests() {
+        use super::*;
+        let black = Color::from_hex("#000000");
+        let white = Color::from_hex("#ffffff");
+        // Lc 106
+        assert_eq!(apca_min_font_size(black, white, 400), Some(14f32));
+        assert_eq!(apca_min_font_size(black, white, 700), Some(10f32));
+        // Lc 63
+        let gray = Color::from_hex("#888888");
+        assert_eq!(apca_min_font_size(gray, white, 400), Some(24f32));
+        assert_eq!(apca_min_font_size(gray, white, 100), Some(72f32));
+        // barely visible pairs are never usable for text
+        let light_gray = Color::from_hex("#eeeeee");
+        assert_eq!(apca_min_font_size(light_gray, white, 900), None);
+    }
+    #[test]
+    fn ensure_contrast_tests() {
+        use super::*;
+        let white = Color::from_hex("#ffffff");
+        let black = Color::from_hex("#000000");
+
+        let passing = Color::from_hex("#333333");
+        assert_eq!(
+            passing.ensure_contrast(white, 4.5f32, ContrastMetric::Wcag),
+            Some(passing)
+        );
+
+        let light_blue = Color::from_hex("#88aaff");
+        let adjusted = light_blue
+            .ensure_contrast(white, 4.5f32, ContrastMetric::Wcag)
+            .unwrap();
+        let ratio = contrast_ratio(adjusted, white);
+        assert!((4.5f32..4.7f32).contains(&ratio), "{}", ratio);
+        let hue_before = Oklch::from(light_blue).h;
+        let hue_after = Oklch::from(adjusted).h;
+        assert!((hue_before - hue_after).abs() < 3.0f32);
+
+        let dark_gray = Color::from_hex("#444444");
+        let adjusted = dark_gray
+            .ensure_contrast(black, 75.0f32, ContrastMetric::Apca)
+            .unwrap();
+        assert!(apca_contrast(adjusted, black) <= -75.0f32);
+
+        let gray = Color::from_hex("#777777");
+        assert_eq!(
+            gray.ensure_contrast(gray, 22.0f32, ContrastMetric::Wcag),
+            None
+        );
+    }
+    #[test]
+    fn best_text_color_tests() {
+        use super::*;
+        let candidates = [
+            Color::from_hex("#000000"),
+            Color::from_hex("#ffffff"),
+            Color::from_hex("#ff0000"),
+        ];
+        let dark = Color::from_hex("#1e1e2e");
+        let light = Color::from_hex("#eff1f5");
+        for metric in [ContrastMetric::Wcag, ContrastMetric::Apca].iter() {
+            assert_eq!(
+                best_text_color(dark, &candidates, *metric),
+                Some(candidates[1])
+            );
+            assert_eq!(
+                best_text_color(light, &candidates, *metric),
+                Some(candidates[0])
+            );
+        }
+        assert_eq!(best_text_color(dark, &[], ContrastMetric::Wcag), None);
+    }
+}
@@ -0,0 +1,450 @@
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use crate::difference::DeltaE;
+use crate::utils;
+use crate::Color;
+
+type Matrix = [[f32; 3]; 3];
+
+const IDENTITY: Matrix = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// kind of color vision deficiency
+/// a severity of 1.0 simulates the dichromacy (protanopia, ...), anything
+/// lower the matching anomalous trichromacy (protanomaly, ...)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CvdKind {
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+/// simulation model
+/// `Machado` is Machado, Oliveira and Fernandes 2009, which is poor for tritanopia
+/// `Brettel` is Brettel, Viénot and Mollon 1997
+/// `Vienot` is Viénot, Brettel and Mollon 1999, the single plane variant of brettel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CvdModel {
+    Machado,
+    Brettel,
+    Vienot,
+}
+
+impl CvdKind {
+    pub const ALL: [CvdKind; 3] = [
+        CvdKind::Protanopia,
+        CvdKind::Deuteranopia,
+        CvdKind::Tritanopia,
+    ];
+
+    /// machado for protanopia and deuteranopia, brettel for tritanopia
+    pub fn default_model(&self) -> CvdModel {
+        match self {
+            CvdKind::Protanopia | CvdKind::Deuteranopia => CvdModel::Machado,
+            CvdKind::Tritanopia => CvdModel::Brettel,
+        }
+    }
+}
+
+// machado 2009 matrices for linear rgb, in 0.1 severity steps
+#[rustfmt::skip]
+const MACHADO_PROTAN: [Matrix; 11] = [
+    IDENTITY,
+    [[0.856167, 0.182038, -0.038205], [0.029342, 0.955115, 0.015544], [-0.002880, -0.001563, 1.004443]],
+    [[0.734766, 0.334872, -0.069637], [0.051840, 0.919198, 0.028963], [-0.004928, -0.004209, 1.009137]],
+    [[0.630323, 0.465641, -0.095964], [0.069181, 0.890046, 0.040773], [-0.006308, -0.007724, 1.014032]],
+    [[0.539009, 0.579343, -0.118352], [0.082546, 0.866121, 0.051332], [-0.007136, -0.011959, 1.019095]],
+    [[0.458064, 0.679578, -0.137642], [0.092785, 0.846313, 0.060902], [-0.007494, -0.016807, 1.024301]],
+    [[0.385450, 0.769005, -0.154455], [0.100526, 0.829802, 0.069673], [-0.007442, -0.022190, 1.029632]],
+    [[0.319627, 0.849633, -0.169261], [0.106241, 0.815969, 0.077790], [-0.007025, -0.028051, 1.035076]],
+    [[0.259411, 0.923008, -0.182420], [0.110296, 0.804340, 0.085364], [-0.006276, -0.034346, 1.040622]],
+    [[0.203876, 0.990338, -0.194214], [0.112975, 0.794542, 0.092483], [-0.005222, -0.041043, 1.046265]],
+    [[0.152286, 1.052583, -0.204868], [0.114503, 0.786281, 0.099216], [-0.003882, -0.048116, 1.051998]],
+];
+
+#[rustfmt::skip]
+const MACHADO_DEUTAN: [Matrix; 11] = [
+    IDENTITY,
+    [[0.866435, 0.177704, -0.044139], [0.049567, 0.939063, 0.011370], [-0.003453, 0.007233, 0.996220]],
+    [[0.760729, 0.319078, -0.079807], [0.090568, 0.889315, 0.020117], [-0.006027, 0.013325, 0.992702]],
+    [[0.675425, 0.433850, -0.109275], [0.125303, 0.847755, 0.026942], [-0.007950, 0.018572, 0.989378]],
+    [[0.605511, 0.528560, -0.134071], [0.155318, 0.812366, 0.032316], [-0.009376, 0.023176, 0.986200]],
+    [[0.547494, 0.607765, -0.155259], [0.181692, 0.781742, 0.036566], [-0.010410, 0.027275, 0.983136]],
+    [[0.498864, 0.674741, -0.173604], [0.205199, 0.754872, 0.039929], [-0.011131, 0.030969, 0.980162]],
+    [[0.457771, 0.731899, -0.189670], [0.226409, 0.731012, 0.042579], [-0.011595, 0.034333, 0.977261]],
+    [[0.422823, 0.781057, -0.203881], [0.245752, 0.709602, 0.044646], [-0.011843, 0.037423, 0.974421]],
+    [[0.392952, 0.823610, -0.216562], [0.263559, 0.690210, 0.046232], [-0.011910, 0.040281, 0.971630]],
+    [[0.367322, 0.860646, -0.227968], [0.280085, 0.672501, 0.047413], [-0.011820, 0.042940, 0.968881]],
+];
+
+#[rustfmt::skip]
+const MACHADO_TRITAN: [Matrix; 11] = [
+    IDENTITY,
+    [[0.926670, 0.092514, -0.019184], [0.021191, 0.964503, 0.014306], [0.008437, 0.054813, 0.936750]],
+    [[0.895720, 0.133330, -0.029050], [0.029997, 0.945400, 0.024603], [0.013027, 0.104707, 0.882266]],
+    [[0.905871, 0.127791, -0.033662], [0.026856, 0.941251, 0.031893], [0.013410, 0.148296, 0.838294]],
+    [[0.948035, 0.089490, -0.037526], [0.014364, 0.946792, 0.038844], [0.010853, 0.193991, 0.795156]],
+    [[1.017277, 0.027029, -0.044306], [-0.006113, 0.958479, 0.047634], [0.006379, 0.248708, 0.744913]],
+    [[1.104996, -0.046633, -0.058363], [-0.032137, 0.971635, 0.060503], [0.001336, 0.317922, 0.680742]],
+    [[1.193214, -0.109812, -0.083402], [-0.058496, 0.979410, 0.079086], [-0.002346, 0.403492, 0.598854]],
+    [[1.257728, -0.139648, -0.118081], [-0.078003, 0.975409, 0.102594], [-0.003316, 0.501214, 0.502102]],
+    [[1.278864, -0.125333, -0.153531], [-0.084748, 0.957674, 0.127074], [-0.000989, 0.601151, 0.399838]],
+    [[1.255528, -0.076749, -0.178779], [-0.078411, 0.930809, 0.147602], [0.004733, 0.691367, 0.303900]],
+];
+
+// viénot 1999 matrices for linear rgb
+#[rustfmt::skip]
+const VIENOT_PROTAN: Matrix = [[0.11238, 0.88762, 0.00000], [0.11238, 0.88762, 0.00000], [0.00401, -0.00401, 1.00000]];
+#[rustfmt::skip]
+const VIENOT_DEUTAN: Matrix = [[0.29275, 0.70725, 0.00000], [0.29275, 0.70725, 0.00000], [-0.02234, 0.02234, 1.00000]];
+#[rustfmt::skip]
+const VIENOT_TRITAN: Matrix = [[1.00000, 0.14461, -0.14461], [0.00000, 0.85924, 0.14076], [0.00000, 0.85924, 0.14076]];
+
+/// brettel 1997 projection onto two half planes, chosen by the side of the separation plane
+struct BrettelParams {
+    first: Matrix,
+    second: Matrix,
+    normal: [f32; 3],
+}
+
+#[rustfmt::skip]
+const BRETTEL_PROTAN: BrettelParams = BrettelParams {
+    first: [[0.14510, 1.20165, -0.34675], [0.10447, 0.85316, 0.04237], [0.00429, -0.00603, 1.00174]],
+    second: [[0.14115, 1.16782, -0.30897], [0.10495, 0.85730, 0.03776], [0.00431, -0.00586, 1.00155]],
+    normal: [0.00048, 0.00416, -0.00464],
+};
+#[rustfmt::skip]
+const BRETTEL_DEUTAN: BrettelParams = BrettelParams {
+    first: [[0.36198, 0.86755, -0.22953], [0.26099, 0.64512, 0.09389], [-0.01975, 0.02686, 0.99289]],
+    second: [[0.37009, 0.88540, -0.25549], [0.25767, 0.63782, 0.10451], [-0.01950, 0.02741, 0.99209]],
+    normal: [-0.00293, -0.00645, 0.00938],
+};
+#[rustfmt::skip]
+const BRETTEL_TRITAN: BrettelParams = BrettelParams {
+    first: [[1.01354, 0.14268, -0.15622], [-0.01181, 0.87561, 0.13619], [0.07707, 0.81208, 0.11085]],
+    second: [[0.93337, 0.19999, -0.13336], [0.05809, 0.82565, 0.11626], [-0.37923, 1.13825, 0.24098]],
+    normal: [0.03960, -0.02831, -0.01129],
+};
+
+fn multiply(matrix: &Matrix, rgb: [f32; 3]) -> [f32; 3] {
+    let row = |r: &[f32; 3]| r[0] * rgb[0] + r[1] * rgb[1] + r[2] * rgb[2];
+    [row(&matrix[0]), row(&matrix[1]), row(&matrix[2])]
+}
+
+fn interpolate(first: &Matrix, second: &Matrix, t: f32) -> Matrix {
+    let mut result = [[0.0f32; 3]; 3];
+    for (row, result_row) in result.iter_mut().enumerate() {
+        for (column, value) in result_row.iter_mut().enumerate() {
+            *value = first[row][column] + (second[row][column] - first[row][column]) * t;
+        }
+    }
+    result
+}
+
+/// a simulation prepared once so slices do not repeat the matrix lookup
+enum Simulation {
+    Matrix(Matrix),
+    Brettel(&'static BrettelParams, f32),
+}
+
+impl Simulation {
+    fn new(kind: CvdKind, severity: f32, model: CvdModel) -> Self {
+        let severity = severity.clamp(0.0f32, 1.0f32);
+        match model {
+            CvdModel::Machado => {
+                let table = match kind {
+                    CvdKind::Protanopia => &MACHADO_PROTAN,
+                    CvdKind::Deuteranopia => &MACHADO_DEUTAN,
+                    CvdKind::Tritanopia => &MACHADO_TRITAN,
+                };
+                let position = severity * 10.0f32;
+                let index = (position.floor() as usize).min(9);
+                let matrix = interpolate(&table[index], &table[index + 1], position - index as f32);
+                Simulation::Matrix(matrix)
+            }
+            CvdModel::Vienot => {
+                let full = match kind {
+                    CvdKind::Protanopia => &VIENOT_PROTAN,
+                    CvdKind::Deuteranopia => &VIENOT_DEUTAN,
+                    CvdKind::Tritanopia => &VIENOT_TRITAN,
+                };
+                Simulation::Matrix(interpolate(&IDENTITY, full, severity))
+            }
+            CvdModel::Brettel => {
+                let params = match kind {
+                    CvdKind::Protanopia => &BRETTEL_PROTAN,
+                    CvdKind::Deuteranopia => &BRETTEL_DEUTAN,
+                    CvdKind::Tritanopia => &BRETTEL_TRITAN,
+                };
+                Simulation::Brettel(params, severity)
+            }
+        }
+    }
+
+    fn apply(&self, color: Color) -> Color {
+        let linear = [
+            utils::srgb_to_linear(color.red as f32 / 255.0f32),
+            utils::srgb_to_linear(color.green as f32 / 255.0f32),
+            utils::srgb_to_linear(color.blue as f32 / 255.0f32),
+        ];
+        let simulated = match self {
+            Simulation::Matrix(matrix) => multiply(matrix, linear),
+            Simulation::Brettel(params, severity) => {
+                let normal = params.normal;
+                let side = linear[0] * normal[0] + linear[1] * normal[1] + linear[2] * normal[2];
+                let matrix = match side >= 0.0f32 {
+                    true => &params.first,
+                    false => &params.second,
+                };
+                let full = multiply(matrix, linear);
+                let mut partial = [0.0f32; 3];
+                for channel in 0..3 {
+                    partial[channel] =
+                        linear[channel] + (full[channel] - linear[channel]) * severity;
+                }
+                partial
+            }
+        };
+        let encode =
+            |channel: f32| utils::unit_f32_to_u8(utils::linear_to_srgb(channel.clamp(0.0, 1.0)));
+        Color {
+            red: encode(simulated[0]),
+            green: encode(simulated[1]),
+            blue: encode(simulated[2]),
+            alpha: color.alpha,
+        }
+    }
+}
+
+impl Color {
+    /// how `self` looks with the given deficiency, using the kind's default model
+    /// `severity` is 0.0 (normal vision) to 1.0 (dichromacy), alpha is kept
+    pub fn simulate_cvd(&self, kind: CvdKind, severity: f32) -> Color {
+        self.simulate_cvd_with(kind, severity, kind.default_model())
+    }
+
+    pub fn simulate_cvd_with(&self, kind: CvdKind, severity: f32, model: CvdModel) -> Color {
+        Simulation::new(kind, severity, model).apply(*self)
+    }
+}
+
+/// simulates every color in place
+pub fn simulate_cvd_slice(colors: &mut [Color], kind: CvdKind, severity: f32) {
+    simulate_cvd_slice_with(colors, kind, severity, kind.default_model())
+}
+
+pub fn simulate_cvd_slice_with(
+    colors: &mut [Color],
+    kind: CvdKind,
+    severity: f32,
+    model: CvdModel,
+) {
+    let simulation = Simulation::new(kind, severity, model);
+    for color in colors.iter_mut() {
+        *color = simulation.apply(*color);
+    }
+}
+
+/// simulates `source` into `destination`, which must have the same length
+pub fn simulate_cvd_into(
+    source: &[Color],
+    destination: &mut [Color],
+    kind: CvdKind,
+    severity: f32,
+) {
+    assert_eq!(
+        source.len(),
+        destination.len(),
+        "Slice lengths differ: {} and {}",
+        source.len(),
+        destination.len()
+    );
+    let simulation = Simulation::new(kind, severity, kind.default_model());
+    for (from, to) in source.iter().zip(destination.iter_mut()) {
+        *to = simulation.apply(*from);
+    }
+}
+
+/// two palette entries that can not be told apart
+/// `vision` is `None` for normal color vision
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Confusion {
+    pub vision: Option<CvdKind>,
+    pub first: usize,
+    pub second: usize,
+    pub delta_e: f32,
+}
+
+/// every confusable pair of a palette, for normal vision and each deficiency
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PaletteReport {
+    pub confusions: Vec<Confusion>,
+}
+
+impl PaletteReport {
+    pub fn passes(&self) -> bool {
+        self.confusions.is_empty()
+    }
+
+    pub fn for_vision(&self, vision: Option<CvdKind>) -> impl Iterator<Item = &Confusion> {
+        self.confusions
+            .iter()
+            .filter(move |confusion| confusion.vision == vision)
+    }
+}
+
+impl Display for PaletteReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for confusion in self.confusions.iter() {
+            let vision = match confusion.vision {
+                None => "normal vision",
+                Some(CvdKind::Protanopia) => "protanopia",
+                Some(CvdKind::Deuteranopia) => "deuteranopia",
+                Some(CvdKind::Tritanopia) => "tritanopia",
+            };
+            writeln!(
+                f,
+                "{}: colors {} and {} are indistinguishable (delta e {:.2})",
+                vision, confusion.first, confusion.second, confusion.delta_e
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// finds every pair of `colors` whose delta e (cie76) falls below `threshold`,
+/// with normal vision and with full protanopia, deuteranopia and tritanopia
+/// alpha is ignored
+pub fn check_palette(colors: &[Color], threshold: f32) -> PaletteReport {
+    check_palette_with(colors, threshold, DeltaE::Cie76)
+}
+
+/// `check_palette` with another difference metric, `threshold` is in the metric's units
+pub fn check_palette_with(colors: &[Color], threshold: f32, metric: DeltaE) -> PaletteReport {
+    let mut report = PaletteReport::default();
+    let visions = [
+        None,
+        Some(CvdKind::Protanopia),
+        Some(CvdKind::Deuteranopia),
+        Some(CvdKind::Tritanopia),
+    ];
+    for vision in visions.iter() {
+        let mut seen = colors.to_vec();
+        if let Some(kind) = vision {
+            simulate_cvd_slice(&mut seen, *kind, 1.0f32);
+        }
+        for first in 0..seen.len() {
+            for second in first + 1..seen.len() {
+                let distance = seen[first].distance(seen[second], metric);
+                if distance < threshold {
+                    report.confusions.push(Confusion {
+                        vision: *vision,
+                        first,
+                        second,
+                        delta_e: distance,
+                    });
+                }
+            }
+        }
+    }
+    report
+}
+
+mod tests {
+    #[test]
+    fn matrix_tests() {
+        use super::*;
+        // every row sums to one so that grays are left untouched
+        for table in [&MACHADO_PROTAN, &MACHADO_DEUTAN, &MACHADO_TRITAN].iter() {
+            for matrix in table.iter() {
+                for row in matrix.iter() {
+                    assert!((row.iter().sum::<f32>() - 1.0f32).abs() < 1e-5);
+                }
+            }
+        }
+    }
+    #[test]
+    fn simulation_tests() {
+        use super::*;
+        use crate::traits::*;
+        let gray = Color::from_hex("#808080");
+        let red = Color::from_hex8("#ff000080");
+        let green = Color::from_hex("#00ff00");
+        for kind in CvdKind::ALL.iter() {
+            for model in [CvdModel::Machado, CvdModel::Brettel, CvdModel::Vienot].iter() {
+                let simulated = gray.simulate_cvd_with(*kind, 1.0f32, *model);
+                assert!((simulated.red as i16 - 128).abs() <= 1, "{:?}", simulated);
+                assert!((simulated.green as i16 - 128).abs() <= 1);
+                assert!((simulated.blue as i16 - 128).abs() <= 1);
+                assert_eq!(red.simulate_cvd_with(*kind, 0.0f32, *model), red);
+            }
+        }
+        assert_eq!(red.simulate_cvd(CvdKind::Protanopia, 1.0f32).alpha, 0x80);
+
+        // red and green collapse onto similar yellows for red-green deficiencies
+        let red = red.simulate_cvd(CvdKind::Deuteranopia, 1.0f32);
+        let green = green.simulate_cvd(CvdKind::Deuteranopia, 1.0f32);
+        for color in [red, green].iter() {
+            assert!(
+                color.blue < color.red && color.blue < color.green,
+                "{:?}",
+                color
+            );
+        }
+
+        let half = Color::from_hex("#ff0000").simulate_cvd(CvdKind::Protanopia, 0.5f32);
+        let full = Color::from_hex("#ff0000").simulate_cvd(CvdKind::Protanopia, 1.0f32);
+        assert!(half.red > full.red);
+    }
+    #[test]
+    fn slice_tests() {
+        use super::*;
+        use crate::traits::*;
+        let colors = [
+            Color::from_hex("#ff0000"),
+            Color::from_hex("#00ff00"),
+            Color::from_hex("#0000ff"),
+        ];
+        let mut in_place = colors;
+        simulate_cvd_slice(&mut in_place, CvdKind::Tritanopia, 0.7f32);
+        let mut copied = [Color::new(); 3];
+        simulate_cvd_into(&colors, &mut copied, CvdKind::Tritanopia, 0.7f32);
+        for index in 0..3 {
+            let single = colors[index].simulate_cvd(CvdKind::Tritanopia, 0.7f32);
+            assert_eq!(in_place[index], single);
+            assert_eq!(copied[index], single);
+        }
+    }
+    #[test]
+    fn check_palette_tests() {
+        use super::*;
+        use crate::traits::*;
+        let distinct = [
+            Color::from_hex("#000000"),
+            Color::from_hex("#ffffff"),
+            Color::from_hex("#0072b2"),
+            Color::from_hex("#e69f00"),
+        ];
+        let report = check_palette(&distinct, 10.0f32);
+        assert!(report.passes(), "{}", report);
+
+        // red and green only fail for the red-green deficiencies
+        let traffic_light = [Color::from_hex("#d62728"), Color::from_hex("#2ca02c")];
+        let report = check_palette(&traffic_light, 10.0f32);
+        assert!(!report.passes());
+        assert_eq!(report.for_vision(None).count(), 0);
+        assert_eq!(report.for_vision(Some(CvdKind::Deuteranopia)).count(), 1);
+        let confusion = report
+            .for_vision(Some(CvdKind::Deuteranopia))
+            .next()
+            .unwrap();
+        assert_eq!((confusion.first, confusion.second), (0, 1));
+        assert!(report.to_string().contains("deuteranopia: colors 0 and 1"));
+
+        let duplicates = [Color::from_hex("#123456"), Color::from_hex("#123457")];
+        assert_eq!(check_palette(&duplicates, 1.0f32).confusions.len(), 4);
+        let report = check_palette_with(&traffic_light, 10.0f32, DeltaE::Ciede2000);
+        assert_eq!(report.for_vision(None).count(), 0);
+        assert_eq!(report.for_vision(Some(CvdKind::Deuteranopia)).count(), 1);
+    }
+}
@@ -0,0 +1,304 @@
+use crate::space::Lab;
+use crate::space::Oklab;
+use crate::Color;
+
+/// weighting set for cie94
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cie94Application {
+    GraphicArts,
+    Textiles,
+}
+
+/// color difference formula
+/// all but `Oklab` are in cielab units where ~2.3 is a just noticeable difference,
+/// `Oklab` is the euclidean distance in oklab, which is roughly 100 times smaller
+/// cie94 and cmc are not symmetric, the receiver of `distance` is the reference
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeltaE {
+    Cie76,
+    Cie94(Cie94Application),
+    Ciede2000,
+    Cmc { lightness: f32, chroma: f32 },
+    Oklab,
+}
+
+impl DeltaE {
+    /// cmc 2:1, for acceptability
+    pub const CMC_ACCEPTABILITY: DeltaE = DeltaE::Cmc {
+        lightness: 2.0,
+        chroma: 1.0,
+    };
+    /// cmc 1:1, for perceptibility
+    pub const CMC_PERCEPTIBILITY: DeltaE = DeltaE::Cmc {
+        lightness: 1.0,
+        chroma: 1.0,
+    };
+
+    /// difference between two lab colors, `Oklab` converts them to oklab in
+    /// floating point, out of gamut values included
+    pub fn between(&self, reference: Lab, sample: Lab) -> f32 {
+        match self {
+            DeltaE::Cie76 => cie76(reference, sample),
+            DeltaE::Cie94(application) => cie94(reference, sample, *application),
+            DeltaE::Ciede2000 => ciede2000(reference, sample),
+            DeltaE::Cmc { lightness, chroma } => cmc(reference, sample, *lightness, *chroma),
+            DeltaE::Oklab => oklab(Oklab::from(reference), Oklab::from(sample)),
+        }
+    }
+}
+
+impl Color {
+    /// perceptual difference between `self` and `other`, alpha is ignored
+    pub fn distance(&self, other: Color, metric: DeltaE) -> f32 {
+        match metric {
+            DeltaE::Oklab => oklab(Oklab::from(*self), Oklab::from(other)),
+            _ => metric.between(Lab::from(*self), Lab::from(other)),
+        }
+    }
+}
+
+fn chroma(a: f64, b: f64) -> f64 {
+    (a * a + b * b).sqrt()
+}
+
+/// hue angle in degrees 0.0 - 360.0
+fn hue(a: f64, b: f64) -> f64 {
+    match a == 0.0 && b == 0.0 {
+        true => 0.0,
+        false => b.atan2(a).to_degrees().rem_euclid(360.0),
+    }
+}
+
+/// cie 1976, plain euclidean distance in cielab
+pub fn cie76(reference: Lab, sample: Lab) -> f32 {
+    ((reference.l - sample.l).powi(2)
+        + (reference.a - sample.a).powi(2)
+        + (reference.b - sample.b).powi(2))
+    .sqrt()
+}
+
+pub fn cie94(reference: Lab, sample: Lab, application: Cie94Application) -> f32 {
+    let (k_l, k_1, k_2) = match application {
+        Cie94Application::GraphicArts => (1.0, 0.045, 0.015),
+        Cie94Application::Textiles => (2.0, 0.048, 0.014),
+    };
+    let (l_1, a_1, b_1) = (reference.l as f64, reference.a as f64, reference.b as f64);
+    let (l_2, a_2, b_2) = (sample.l as f64, sample.a as f64, sample.b as f64);
+    let c_1 = chroma(a_1, b_1);
+    let c_2 = chroma(a_2, b_2);
+    let delta_l = l_1 - l_2;
+    let delta_c = c_1 - c_2;
+    let delta_h_squared = ((a_1 - a_2).powi(2) + (b_1 - b_2).powi(2) - delta_c * delta_c).max(0.0);
+    let s_c = 1.0 + k_1 * c_1;
+    let s_h = 1.0 + k_2 * c_1;
+    ((delta_l / k_l).powi(2) + (delta_c / s_c).powi(2) + delta_h_squared / (s_h * s_h)).sqrt()
+        as f32
+}
+
+/// ciede2000 with unit weighting factors
+pub fn ciede2000(reference: Lab, sample: Lab) -> f32 {
+    let (l_1, a_1, b_1) = (reference.l as f64, reference.a as f64, reference.b as f64);
+    let (l_2, a_2, b_2) = (sample.l as f64, sample.a as f64, sample.b as f64);
+
+    let c_bar = (chroma(a_1, b_1) + chroma(a_2, b_2)) / 2.0;
+    let c_bar_7 = c_bar.powi(7);
+    let g = 0.5 * (1.0 - (c_bar_7 / (c_bar_7 + 25.0f64.powi(7))).sqrt());
+    let a_1 = (1.0 + g) * a_1;
+    let a_2 = (1.0 + g) * a_2;
+    let c_1 = chroma(a_1, b_1);
+    let c_2 = chroma(a_2, b_2);
+    let h_1 = hue(a_1, b_1);
+    let h_2 = hue(a_2, b_2);
+
+    let delta_l = l_2 - l_1;
+    let delta_c = c_2 - c_1;
+    let delta_h = match c_1 * c_2 == 0.0 {
+        true => 0.0,
+        false => {
+            let difference = h_2 - h_1;
+            if difference.abs() <= 180.0 {
+                difference
+            } else if difference > 180.0 {
+                difference - 360.0
+            } else {
+                difference + 360.0
+            }
+        }
+    };
+    let delta_h = 2.0 * (c_1 * c_2).sqrt() * (delta_h / 2.0).to_radians().sin();
+
+    let l_bar = (l_1 + l_2) / 2.0;
+    let c_bar = (c_1 + c_2) / 2.0;
+    let h_bar = if c_1 * c_2 == 0.0 {
+        h_1 + h_2
+    } else if (h_1 - h_2).abs() <= 180.0 {
+        (h_1 + h_2) / 2.0
+    } else if h_1 + h_2 < 360.0 {
+        (h_1 + h_2 + 360.0) / 2.0
+    } else {
+        (h_1 + h_2 - 360.0) / 2.0
+    };
+
+    let t = 1.0 - 0.17 * (h_bar - 30.0).to_radians().cos()
+        + 0.24 * (2.0 * h_bar).to_radians().cos()
+        + 0.32 * (3.0 * h_bar + 6.0).to_radians().cos()
+        - 0.20 * (4.0 * h_bar - 63.0).to_radians().cos();
+    let delta_theta = 30.0 * (-((h_bar - 275.0) / 25.0).powi(2)).exp();
+    let c_bar_7 = c_bar.powi(7);
+    let r_c = 2.0 * (c_bar_7 / (c_bar_7 + 25.0f64.powi(7))).sqrt();
+    let s_l = 1.0 + 0.015 * (l_bar - 50.0).powi(2) / (20.0 + (l_bar - 50.0).powi(2)).sqrt();
+    let s_c = 1.0 + 0.045 * c_bar;
+    let s_h = 1.0 + 0.015 * c_bar * t;
+    let r_t = -(2.0 * delta_theta).to_radians().sin() * r_c;
+
+    let lightness = delta_l / s_l;
+    let chroma = delta_c / s_c;
+    let hue = delta_h / s_h;
+    (lightness * lightness + chroma * chroma + hue * hue + r_t * chroma * hue).sqrt() as f32
+}
+
+/// cmc l:c, see `DeltaE::CMC_ACCEPTABILITY` and `DeltaE::CMC_PERCEPTIBILITY`
+pub fn cmc(reference: Lab, sample: Lab, lightness: f32, chroma_weight: f32) -> f32 {
+    let (l_1, a_1, b_1) = (reference.l as f64, reference.a as f64, reference.b as f64);
+    let (l_2, a_2, b_2) = (sample.l as f64, sample.a as f64, sample.b as f64);
+    let c_1 = chroma(a_1, b_1);
+    let c_2 = chroma(a_2, b_2);
+    let delta_l = l_1 - l_2;
+    let delta_c = c_1 - c_2;
+    let delta_h_squared = ((a_1 - a_2).powi(2) + (b_1 - b_2).powi(2) - delta_c * delta_c).max(0.0);
+
+    let s_l = match l_1 < 16.0 {
+        true => 0.511,
+        false => 0.040975 * l_1 / (1.0 + 0.01765 * l_1),
+    };
+    let s_c = 0.0638 * c_1 / (1.0 + 0.0131 * c_1) + 0.638;
+    let h_1 = hue(a_1, b_1);
+    let t = match (164.0..=345.0).contains(&h_1) {
+        true => 0.56 + (0.2 * (h_1 + 168.0).to_radians().cos()).abs(),
+        false => 0.36 + (0.4 * (h_1 + 35.0).to_radians().cos()).abs(),
+    };
+    let f = (c_1.powi(4) / (c_1.powi(4) + 1900.0)).sqrt();
+    let s_h = s_c * (f * t + 1.0 - f);
+
+    ((delta_l / (lightness as f64 * s_l)).powi(2)
+        + (delta_c / (chroma_weight as f64 * s_c)).powi(2)
+        + delta_h_squared / (s_h * s_h))
+        .sqrt() as f32
+}
+
+/// euclidean distance in oklab
+pub fn oklab(reference: Oklab, sample: Oklab) -> f32 {
+    ((reference.l - sample.l).powi(2)
+        + (reference.a - sample.a).powi(2)
+        + (reference.b - sample.b).powi(2))
+    .sqrt()
+}
+
+mod tests {
+    #[test]
+    fn ciede2000_sharma_tests() {
+        use super::*;
+        // Sharma, Wu and Dalal 2005, table 1
+        #[rustfmt::skip]
+        let dataset: [(f32, f32, f32, f32, f32, f32, f32); 34] = [
+            (50.0000, 2.6772, -79.7751, 50.0000, 0.0000, -82.7485, 2.0425),
+            (50.0000, 3.1571, -77.2803, 50.0000, 0.0000, -82.7485, 2.8615),
+            (50.0000, 2.8361, -74.0200, 50.0000, 0.0000, -82.7485, 3.4412),
+            (50.0000, -1.3802, -84.2814, 50.0000, 0.0000, -82.7485, 1.0000),
+            (50.0000, -1.1848, -84.8006, 50.0000, 0.0000, -82.7485, 1.0000),
+            (50.0000, -0.9009, -85.5211, 50.0000, 0.0000, -82.7485, 1.0000),
+            (50.0000, 0.0000, 0.0000, 50.0000, -1.0000, 2.0000, 2.3669),
+            (50.0000, -1.0000, 2.0000, 50.0000, 0.0000, 0.0000, 2.3669),
+            (50.0000, 2.4900, -0.0010, 50.0000, -2.4900, 0.0009, 7.1792),
+            (50.0000, 2.4900, -0.0010, 50.0000, -2.4900, 0.0010, 7.1792),
+            (50.0000, 2.4900, -0.0010, 50.0000, -2.4900, 0.0011, 7.2195),
+            (50.0000, 2.4900, -0.0010, 50.0000, -2.4900, 0.0012, 7.2195),
+            (50.0000, -0.0010, 2.4900, 50.0000, 0.0009, -2.4900, 4.8045),
+            (50.0000, -0.0010, 2.4900, 50.0000, 0.0010, -2.4900, 4.8045),
+            (50.0000, -0.0010, 2.4900, 50.0000, 0.0011, -2.4900, 4.7461),
+            (50.0000, 2.5000, 0.0000, 50.0000, 0.0000, -2.5000, 4.3065),
+            (50.0000, 2.5000, 0.0000, 73.0000, 25.0000, -18.0000, 27.1492),
+            (50.0000, 2.5000, 0.0000, 61.0000, -5.0000, 29.0000, 22.8977),
+            (50.0000, 2.5000, 0.0000, 56.0000, -27.0000, -3.0000, 31.9030),
+            (50.0000, 2.5000, 0.0000, 58.0000, 24.0000, 15.0000, 19.4535),
+            (50.0000, 2.5000, 0.0000, 50.0000, 3.1736, 0.5854, 1.0000),
+            (50.0000, 2.5000, 0.0000, 50.0000, 3.2972, 0.0000, 1.0000),
+            (50.0000, 2.5000, 0.0000, 50.0000, 1.8634, 0.5757, 1.0000),
+            (50.0000, 2.5000, 0.0000, 50.0000, 3.2592, 0.3350, 1.0000),
+            (60.2574, -34.0099, 36.2677, 60.4626, -34.1751, 39.4387, 1.2644),
+            (63.0109, -31.0961, -5.8663, 62.8187, -29.7946, -4.0864, 1.2630),
+            (61.2901, 3.7196, -5.3901, 61.4292, 2.2480, -4.9620, 1.8731),
+            (35.0831, -44.1164, 3.7933, 35.0232, -40.0716, 1.5901, 1.8645),
+            (22.7233, 20.0904, -46.6940, 23.0331, 14.9730, -42.5619, 2.0373),
+            (36.4612, 47.8580, 18.3852, 36.2715, 50.5065, 21.2231, 1.4146),
+            (90.8027, -2.0831, 1.4410, 91.1528, -1.6435, 0.0447, 1.4441),
+            (90.9257, -0.5406, -0.9208, 88.6381, -0.8985, -0.7239, 1.5381),
+            (6.7747, -0.2908, -2.4247, 5.8714, -0.0985, -2.2286, 0.6377),
+            (2.0776, 0.0795, -1.1350, 0.9033, -0.0636, -0.5514, 0.9082),
+        ];
+        for (index, row) in dataset.iter().enumerate() {
+            let first = Lab::new(row.0, row.1, row.2);
+            let second = Lab::new(row.3, row.4, row.5);
+            let forward = ciede2000(first, second);
+            let backward = ciede2000(second, first);
+            assert!(
+                (forward - row.6).abs() < 1e-4,
+                "pair {}: {}",
+                index + 1,
+                forward
+            );
+            assert!(
+                (backward - row.6).abs() < 1e-4,
+                "pair {}: {}",
+                index + 1,
+                backward
+            );
+        }
+    }
+    #[test]
+    fn metric_tests() {
+        use super::*;
+        let first = Lab::new(0.9, 16.3, -2.22);
+        let second = Lab::new(0.7, 14.2, -1.80);
+        let graphic_arts = DeltaE::Cie94(Cie94Application::GraphicArts);
+        assert!((DeltaE::Cie76.between(first, second) - 2.151).abs() < 1e-3);
+        assert!((graphic_arts.between(first, second) - 1.249).abs() < 1e-3);
+        assert!((DeltaE::Ciede2000.between(first, second) - 1.523).abs() < 1e-3);
+        assert!((DeltaE::CMC_ACCEPTABILITY.between(first, second) - 1.443).abs() < 1e-3);
+
+        // oklab from lab agrees with oklab from the color and keeps differences
+        // finer than 8 bits or outside srgb
+        let orange = Color::rgb(255, 136, 0);
+        let navy = Color::rgb(0, 0, 128);
+        let between = DeltaE::Oklab.between(Lab::from(orange), Lab::from(navy));
+        assert!((between - orange.distance(navy, DeltaE::Oklab)).abs() < 1e-4);
+        assert!(DeltaE::Oklab.between(first, Lab::new(0.9, 16.3, -2.2)) > 0.0);
+        let vivid = Lab::new(100.0, 150.0, 150.0);
+        let more_vivid = Lab::new(100.0, 160.0, 160.0);
+        assert_eq!(Color::from(vivid), Color::from(more_vivid));
+        assert!(DeltaE::Oklab.between(vivid, more_vivid) > 1e-3);
+    }
+    #[test]
+    fn color_distance_tests() {
+        use super::*;
+        use crate::traits::*;
+        let black = Color::from_hex("#000000");
+        let white = Color::from_hex("#ffffff");
+        let metrics = [
+            DeltaE::Cie76,
+            DeltaE::Cie94(Cie94Application::GraphicArts),
+            DeltaE::Cie94(Cie94Application::Textiles),
+            DeltaE::Ciede2000,
+            DeltaE::CMC_ACCEPTABILITY,
+            DeltaE::CMC_PERCEPTIBILITY,
+            DeltaE::Oklab,
+        ];
+        for metric in metrics.iter() {
+            assert!(white.distance(white, *metric).abs() < 1e-3, "{:?}", metric);
+            assert!(black.distance(white, *metric) > 0.0, "{:?}", metric);
+        }
+        assert!((black.distance(white, DeltaE::Cie76) - 100.0).abs() < 1e-2);
+        assert!((black.distance(white, DeltaE::Ciede2000) - 100.0).abs() < 1e-2);
+        assert!((black.distance(white, DeltaE::Oklab) - 1.0).abs() < 1e-3);
+    }
+}
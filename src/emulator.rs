@@ -0,0 +1,612 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::scheme::from_named;
+use crate::scheme::parse_color;
+use crate::scheme::TerminalScheme;
+use crate::traits::*;
+use crate::Color;
+
+const ANSI_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+static ITERM_COLOR_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?s)<key>([^<]*)</key>\s*<dict>(.*?)</dict>"#).unwrap());
+
+static ITERM_COMPONENT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"<key>([^<]*)</key>\s*<(?:real|integer)>([^<]*)</(?:real|integer)>"#).unwrap()
+});
+
+/// `line` without a `#` comment, ignoring `#` inside quotes
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (position, character) in line.char_indices() {
+        match (quote, character) {
+            (None, '"') | (None, '\'') => quote = Some(character),
+            (Some(open), _) if open == character => quote = None,
+            (None, '#') => return &line[..position],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    for quote in ['"', '\''].iter() {
+        if let Some(inner) = value
+            .strip_prefix(*quote)
+            .and_then(|rest| rest.strip_suffix(*quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+fn toml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// `(section, key, value)` for every `key = value` line of a toml or ini file,
+/// comments are dropped and arrays spread over several lines are joined
+fn assignments(s: &str) -> Vec<(String, String, String)> {
+    let mut section = String::new();
+    let mut result = Vec::new();
+    let mut pending: Option<(String, String)> = None;
+    for line in s.lines() {
+        let line = strip_comment(line).trim();
+        if let Some((key, mut value)) = pending.take() {
+            value.push_str(line);
+            match line.contains(']') {
+                true => result.push((section.clone(), key, value)),
+                false => pending = Some((key, value)),
+            }
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            section = line
+                .trim_matches(|c| c == '[' || c == ']')
+                .trim()
+                .to_string();
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            let key = unquote(key).to_string();
+            let value = value.trim().to_string();
+            match value.starts_with('[') && !value.contains(']') {
+                true => pending = Some((key, value)),
+                false => result.push((section.clone(), key, value)),
+            }
+        }
+    }
+    result
+}
+
+/// the elements of a one level toml array
+fn array(value: &str) -> Vec<&str> {
+    value
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(unquote)
+        .filter(|element| !element.is_empty())
+        .collect()
+}
+
+/// a color written as `#rrggbb`, `rrggbb` or `0xrrggbb`
+fn config_color(name: &str, value: &str) -> Result<Color, Box<dyn Error>> {
+    let value = unquote(value);
+    let digits = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix('#'))
+        .unwrap_or(value);
+    parse_color(name, &format!("#{}", digits))
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WindowsTerminalScheme {
+    name: String,
+    background: String,
+    foreground: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cursor_color: Option<String>,
+    black: String,
+    red: String,
+    green: String,
+    yellow: String,
+    blue: String,
+    purple: String,
+    cyan: String,
+    white: String,
+    bright_black: String,
+    bright_red: String,
+    bright_green: String,
+    bright_yellow: String,
+    bright_blue: String,
+    bright_purple: String,
+    bright_cyan: String,
+    bright_white: String,
+}
+
+impl TerminalScheme {
+    /// alacritty 0.13+ toml `colors` tables
+    pub fn to_alacritty(&self) -> String {
+        let mut lines = vec![
+            "[colors.primary]".to_string(),
+            format!("background = \"{}\"", self.background.hex()),
+            format!("foreground = \"{}\"", self.foreground.hex()),
+            String::new(),
+            "[colors.cursor]".to_string(),
+            format!("text = \"{}\"", self.background.hex()),
+            format!("cursor = \"{}\"", self.cursor.hex()),
+        ];
+        for (section, offset) in [("normal", 0), ("bright", 8)].iter() {
+            lines.push(String::new());
+            lines.push(format!("[colors.{}]", section));
+            for (index, name) in ANSI_NAMES.iter().enumerate() {
+                lines.push(format!(
+                    "{} = \"{}\"",
+                    name,
+                    self.colors[offset + index].hex()
+                ));
+            }
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// accepts `#rrggbb` and the older `0xrrggbb` values
+    pub fn from_alacritty(s: &str) -> Result<TerminalScheme, Box<dyn Error>> {
+        let mut values = HashMap::new();
+        for (section, key, value) in assignments(s) {
+            let name = match (section.as_str(), key.as_str()) {
+                ("colors.primary", "background") | ("colors.primary", "foreground") => key.clone(),
+                ("colors.cursor", "cursor") => key.clone(),
+                ("colors.normal", _) | ("colors.bright", _) => {
+                    let offset = match section.as_str() {
+                        "colors.normal" => 0,
+                        _ => 8,
+                    };
+                    match ANSI_NAMES.iter().position(|name| *name == key) {
+                        Some(index) => format!("color{}", offset + index),
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            let color = config_color(&format!("{}.{}", section, key), &value)?;
+            values.insert(name, color);
+        }
+        from_named(&values)
+    }
+
+    /// kitty `.conf` color settings
+    pub fn to_kitty(&self) -> String {
+        let mut lines = vec![
+            format!("foreground {}", self.foreground.hex()),
+            format!("background {}", self.background.hex()),
+            format!("cursor {}", self.cursor.hex()),
+            format!("cursor_text_color {}", self.background.hex()),
+        ];
+        for (index, color) in self.colors.iter().enumerate() {
+            lines.push(format!("color{} {}", index, color.hex()));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// other kitty settings are ignored
+    pub fn from_kitty(s: &str) -> Result<TerminalScheme, Box<dyn Error>> {
+        let mut values = HashMap::new();
+        for line in s.lines().map(str::trim) {
+            if line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+                let known = matches!(key, "foreground" | "background" | "cursor")
+                    || (0..16).any(|index| key == format!("color{}", index));
+                if known {
+                    values.insert(key.to_string(), config_color(key, value)?);
+                }
+            }
+        }
+        from_named(&values)
+    }
+
+    /// a wezterm color scheme file, `name` goes in the metadata table
+    pub fn to_wezterm(&self, name: &str) -> String {
+        let list = |colors: &[Color]| {
+            colors
+                .iter()
+                .map(|color| format!("\"{}\"", color.hex()))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        let lines = vec![
+            "[colors]".to_string(),
+            format!("foreground = \"{}\"", self.foreground.hex()),
+            format!("background = \"{}\"", self.background.hex()),
+            format!("cursor_bg = \"{}\"", self.cursor.hex()),
+            format!("cursor_border = \"{}\"", self.cursor.hex()),
+            format!("cursor_fg = \"{}\"", self.background.hex()),
+            format!("ansi = [{}]", list(&self.colors[..8])),
+            format!("brights = [{}]", list(&self.colors[8..])),
+            String::new(),
+            "[metadata]".to_string(),
+            format!("name = {}", toml_string(name)),
+        ];
+        lines.join("\n") + "\n"
+    }
+
+    pub fn from_wezterm(s: &str) -> Result<TerminalScheme, Box<dyn Error>> {
+        let mut values = HashMap::new();
+        for (section, key, value) in assignments(s) {
+            if section != "colors" {
+                continue;
+            }
+            match key.as_str() {
+                "foreground" | "background" => {
+                    values.insert(key.clone(), config_color(&key, &value)?);
+                }
+                "cursor_bg" => {
+                    values.insert("cursor".to_string(), config_color(&key, &value)?);
+                }
+                "ansi" | "brights" => {
+                    let offset = match key.as_str() {
+                        "ansi" => 0,
+                        _ => 8,
+                    };
+                    for (index, element) in array(&value).iter().take(8).enumerate() {
+                        let color = config_color(&format!("{}[{}]", key, index), element)?;
+                        values.insert(format!("color{}", offset + index), color);
+                    }
+                }
+                _ => {}
+            }
+        }
+        from_named(&values)
+    }
+
+    /// foot `foot.ini` sections, foot writes colors without the `#`
+    pub fn to_foot(&self) -> String {
+        let mut lines = vec![
+            "[cursor]".to_string(),
+            format!(
+                "color={} {}",
+                self.background.hex_stripped(),
+                self.cursor.hex_stripped()
+            ),
+            String::new(),
+            "[colors]".to_string(),
+            format!("foreground={}", self.foreground.hex_stripped()),
+            format!("background={}", self.background.hex_stripped()),
+        ];
+        for (prefix, offset) in [("regular", 0), ("bright", 8)].iter() {
+            for index in 0..8 {
+                lines.push(format!(
+                    "{}{}={}",
+                    prefix,
+                    index,
+                    self.colors[offset + index].hex_stripped()
+                ));
+            }
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// the cursor is read from `[cursor] color` or the newer `[colors] cursor`
+    pub fn from_foot(s: &str) -> Result<TerminalScheme, Box<dyn Error>> {
+        let mut values = HashMap::new();
+        for (section, key, value) in assignments(s) {
+            let name = match (section.as_str(), key.as_str()) {
+                ("cursor", "color") | ("colors", "cursor") => {
+                    let cursor = value.split_whitespace().nth(1).unwrap_or_default();
+                    values.insert("cursor".to_string(), config_color(&key, cursor)?);
+                    continue;
+                }
+                ("colors", "foreground") | ("colors", "background") => key.clone(),
+                ("colors", _) => {
+                    let (offset, index) = match key.strip_prefix("regular") {
+                        Some(index) => (0, index),
+                        None => match key.strip_prefix("bright") {
+                            Some(index) => (8, index),
+                            None => continue,
+                        },
+                    };
+                    match index.parse::<usize>() {
+                        Ok(index) if index < 8 => format!("color{}", offset + index),
+                        _ => continue,
+                    }
+                }
+                _ => continue,
+            };
+            values.insert(name, config_color(&key, &value)?);
+        }
+        from_named(&values)
+    }
+
+    /// a scheme object for the `schemes` list of windows terminal's settings.json
+    pub fn to_windows_terminal(&self, name: &str) -> String {
+        let hex = |index: usize| self.colors[index].hex();
+        let scheme = WindowsTerminalScheme {
+            name: name.to_string(),
+            background: self.background.hex(),
+            foreground: self.foreground.hex(),
+            cursor_color: Some(self.cursor.hex()),
+            black: hex(0),
+            red: hex(1),
+            green: hex(2),
+            yellow: hex(3),
+            blue: hex(4),
+            purple: hex(5),
+            cyan: hex(6),
+            white: hex(7),
+            bright_black: hex(8),
+            bright_red: hex(9),
+            bright_green: hex(10),
+            bright_yellow: hex(11),
+            bright_blue: hex(12),
+            bright_purple: hex(13),
+            bright_cyan: hex(14),
+            bright_white: hex(15),
+        };
+        serde_json::to_string_pretty(&scheme).unwrap() + "\n"
+    }
+
+    pub fn from_windows_terminal(s: &str) -> Result<TerminalScheme, Box<dyn Error>> {
+        let scheme: WindowsTerminalScheme = serde_json::from_str(s)?;
+        let colors = [
+            &scheme.black,
+            &scheme.red,
+            &scheme.green,
+            &scheme.yellow,
+            &scheme.blue,
+            &scheme.purple,
+            &scheme.cyan,
+            &scheme.white,
+            &scheme.bright_black,
+            &scheme.bright_red,
+            &scheme.bright_green,
+            &scheme.bright_yellow,
+            &scheme.bright_blue,
+            &scheme.bright_purple,
+            &scheme.bright_cyan,
+            &scheme.bright_white,
+        ];
+        let mut values = HashMap::new();
+        values.insert(
+            "background".to_string(),
+            parse_color("background", &scheme.background)?,
+        );
+        values.insert(
+            "foreground".to_string(),
+            parse_color("foreground", &scheme.foreground)?,
+        );
+        if let Some(cursor) = &scheme.cursor_color {
+            values.insert("cursor".to_string(), parse_color("cursorColor", cursor)?);
+        }
+        for (index, value) in colors.iter().enumerate() {
+            let name = format!("color{}", index);
+            let color = parse_color(&name, value)?;
+            values.insert(name, color);
+        }
+        from_named(&values)
+    }
+
+    /// an iterm2 `.itermcolors` plist in srgb, components are written as
+    /// `channel / 255` so reading them back gives the same bytes
+    pub fn to_iterm2(&self) -> String {
+        let mut entries: Vec<(String, Color)> = self
+            .colors
+            .iter()
+            .enumerate()
+            .map(|(index, color)| (format!("Ansi {} Color", index), *color))
+            .collect();
+        entries.push(("Background Color".to_string(), self.background));
+        entries.push(("Cursor Color".to_string(), self.cursor));
+        entries.push(("Cursor Text Color".to_string(), self.background));
+        entries.push(("Foreground Color".to_string(), self.foreground));
+        entries.sort_by(|first, second| first.0.cmp(&second.0));
+
+        let mut xml = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" ",
+            "\"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n",
+            "<plist version=\"1.0\">\n",
+            "<dict>\n",
+        ));
+        let component = |value: u8| value as f64 / 255.0f64;
+        for (key, color) in entries.iter() {
+            xml.push_str(&format!("\t<key>{}</key>\n\t<dict>\n", key));
+            xml.push_str(&format!(
+                "\t\t<key>Alpha Component</key>\n\t\t<real>{}</real>\n",
+                component(color.alpha)
+            ));
+            xml.push_str(&format!(
+                "\t\t<key>Blue Component</key>\n\t\t<real>{}</real>\n",
+                component(color.blue)
+            ));
+            xml.push_str("\t\t<key>Color Space</key>\n\t\t<string>sRGB</string>\n");
+            xml.push_str(&format!(
+                "\t\t<key>Green Component</key>\n\t\t<real>{}</real>\n",
+                component(color.green)
+            ));
+            xml.push_str(&format!(
+                "\t\t<key>Red Component</key>\n\t\t<real>{}</real>\n",
+                component(color.red)
+            ));
+            xml.push_str("\t</dict>\n");
+        }
+        xml.push_str("</dict>\n</plist>\n");
+        xml
+    }
+
+    /// components are treated as srgb whatever the `Color Space` says, a
+    /// missing alpha is opaque
+    pub fn from_iterm2(s: &str) -> Result<TerminalScheme, Box<dyn Error>> {
+        let mut values = HashMap::new();
+        for capture in ITERM_COLOR_REGEX.captures_iter(s) {
+            let key = capture[1].trim();
+            let name = match key {
+                "Background Color" => "background".to_string(),
+                "Foreground Color" => "foreground".to_string(),
+                "Cursor Color" => "cursor".to_string(),
+                _ => match key
+                    .strip_prefix("Ansi ")
+                    .and_then(|rest| rest.strip_suffix(" Color"))
+                    .and_then(|index| index.parse::<usize>().ok())
+                {
+                    Some(index) if index < 16 => format!("color{}", index),
+                    _ => continue,
+                },
+            };
+            let mut components = [None, None, None, Some(255u8)];
+            for component in ITERM_COMPONENT_REGEX.captures_iter(&capture[2]) {
+                let slot = match component[1].trim() {
+                    "Red Component" => 0,
+                    "Green Component" => 1,
+                    "Blue Component" => 2,
+                    "Alpha Component" => 3,
+                    _ => continue,
+                };
+                let value = component[2]
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|error| format!("Invalid component in {}: {}", key, error))?;
+                components[slot] = Some((value.clamp(0.0f64, 1.0f64) * 255.0f64).round() as u8);
+            }
+            match components {
+                [Some(red), Some(green), Some(blue), Some(alpha)] => {
+                    values.insert(name, Color::from_tuple_alpha((red, green, blue, alpha)));
+                }
+                _ => return Err(format!("Missing components in {}", key).into()),
+            }
+        }
+        from_named(&values)
+    }
+}
+
+mod tests {
+    #[allow(dead_code)]
+    fn tomorrow_night() -> super::TerminalScheme {
+        use crate::traits::*;
+        let hexes = [
+            "#1d1f21", "#cc6666", "#b5bd68", "#f0c674", "#81a2be", "#b294bb", "#8abeb7", "#c5c8c6",
+            "#969896", "#d54e53", "#b9ca4a", "#e7c547", "#7aa6da", "#c397d8", "#70c0b1", "#eaeaea",
+        ];
+        let mut colors = [crate::Color::new(); 16];
+        for (color, hex) in colors.iter_mut().zip(hexes.iter()) {
+            *color = crate::Color::from_hex(hex);
+        }
+        super::TerminalScheme {
+            background: crate::Color::from_hex("#1d1f21"),
+            foreground: crate::Color::from_hex("#c5c8c6"),
+            cursor: crate::Color::from_hex("#aeafad"),
+            colors,
+        }
+    }
+
+    #[test]
+    fn golden_tests() {
+        use super::*;
+        let scheme = tomorrow_night();
+        let name = "Tomorrow \"Night\"";
+
+        let alacritty = include_str!("../tests/golden/alacritty.toml");
+        assert_eq!(scheme.to_alacritty(), alacritty);
+        assert_eq!(TerminalScheme::from_alacritty(alacritty).unwrap(), scheme);
+
+        let kitty = include_str!("../tests/golden/kitty.conf");
+        assert_eq!(scheme.to_kitty(), kitty);
+        assert_eq!(TerminalScheme::from_kitty(kitty).unwrap(), scheme);
+
+        let wezterm = include_str!("../tests/golden/wezterm.toml");
+        assert_eq!(scheme.to_wezterm(name), wezterm);
+        assert_eq!(TerminalScheme::from_wezterm(wezterm).unwrap(), scheme);
+
+        let foot = include_str!("../tests/golden/foot.ini");
+        assert_eq!(scheme.to_foot(), foot);
+        assert_eq!(TerminalScheme::from_foot(foot).unwrap(), scheme);
+
+        let windows_terminal = include_str!("../tests/golden/windows-terminal.json");
+        assert_eq!(scheme.to_windows_terminal(name), windows_terminal);
+        assert_eq!(
+            TerminalScheme::from_windows_terminal(windows_terminal).unwrap(),
+            scheme
+        );
+
+        let iterm2 = include_str!("../tests/golden/tomorrow-night.itermcolors");
+        assert_eq!(scheme.to_iterm2(), iterm2);
+        assert_eq!(TerminalScheme::from_iterm2(iterm2).unwrap(), scheme);
+    }
+    #[test]
+    fn import_tests() {
+        use super::*;
+        let scheme = tomorrow_night();
+
+        // older alacritty values, comments and a multi-line wezterm array
+        let alacritty = scheme
+            .to_alacritty()
+            .replace("\"#", "'0x")
+            .replace("\"\n", "' # comment\n");
+        assert_eq!(TerminalScheme::from_alacritty(&alacritty).unwrap(), scheme);
+        let wezterm = scheme.to_wezterm("x").replace(", ", ",\n    ");
+        assert_eq!(TerminalScheme::from_wezterm(&wezterm).unwrap(), scheme);
+        let foot = scheme
+            .to_foot()
+            .replace("[cursor]\ncolor=", "[colors]\ncursor=");
+        assert_eq!(TerminalScheme::from_foot(&foot).unwrap(), scheme);
+
+        let kitty = scheme.to_kitty().replace("color9 ", "# color9 ");
+        assert!(TerminalScheme::from_kitty(&kitty)
+            .unwrap_err()
+            .to_string()
+            .contains("color9"));
+        assert!(TerminalScheme::from_windows_terminal("{\"name\": \"x\"}").is_err());
+    }
+    #[test]
+    fn iterm2_precision_tests() {
+        use super::*;
+        let mut scheme = tomorrow_night();
+        for value in 0..=255u8 {
+            scheme.colors[(value % 16) as usize] =
+                Color::from_tuple_alpha((value, 255 - value, value / 3, value));
+            let parsed = TerminalScheme::from_iterm2(&scheme.to_iterm2()).unwrap();
+            assert_eq!(parsed, scheme);
+        }
+
+        // iterm2 itself writes single precision values widened to double
+        let background = r#"	<key>Background Color</key>
+	<dict>
+		<key>Blue Component</key>
+		<real>0.12941177189350128</real>
+		<key>Color Space</key>
+		<string>Calibrated</string>
+		<key>Green Component</key>
+		<real>0.12156862765550613</real>
+		<key>Red Component</key>
+		<real>0.11372549086809158</real>
+	</dict>
+"#;
+        let partial = format!(
+            "<plist version=\"1.0\">\n<dict>\n{}</dict>\n</plist>\n",
+            background
+        );
+        let error = TerminalScheme::from_iterm2(&partial).unwrap_err();
+        assert_eq!(error.to_string(), "Missing foreground");
+        let full = scheme
+            .to_iterm2()
+            .replace("<key>Background Color</key>", "<key>Ignored Color</key>")
+            .replacen("<dict>\n", &format!("<dict>\n{}", background), 1);
+        let parsed = TerminalScheme::from_iterm2(&full).unwrap();
+        assert_eq!(parsed.background, Color::from_hex("#1d1f21"));
+    }
+}
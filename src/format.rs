@@ -0,0 +1,214 @@
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Write;
+
+use crate::traits::*;
+use crate::utils;
+
+/// the string forms of `MakeString`, named after its methods
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorFormat {
+    Hex,
+    HexStripped,
+    Hex8,
+    Hex8Stripped,
+    Rgb,
+    RgbStripped,
+    RgbPercentage,
+    RgbPercentageRounded,
+    Rgba,
+    RgbaStripped,
+    RgbaPercentage,
+    RgbaPercentageRounded,
+    Xrgba,
+}
+
+impl ColorFormat {
+    pub const ALL: [ColorFormat; 13] = [
+        ColorFormat::Hex,
+        ColorFormat::HexStripped,
+        ColorFormat::Hex8,
+        ColorFormat::Hex8Stripped,
+        ColorFormat::Rgb,
+        ColorFormat::RgbStripped,
+        ColorFormat::RgbPercentage,
+        ColorFormat::RgbPercentageRounded,
+        ColorFormat::Rgba,
+        ColorFormat::RgbaStripped,
+        ColorFormat::RgbaPercentage,
+        ColorFormat::RgbaPercentageRounded,
+        ColorFormat::Xrgba,
+    ];
+
+    /// the `MakeString` method name, `rgba_percentage` and so on
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorFormat::Hex => "hex",
+            ColorFormat::HexStripped => "hex_stripped",
+            ColorFormat::Hex8 => "hex8",
+            ColorFormat::Hex8Stripped => "hex8_stripped",
+            ColorFormat::Rgb => "rgb",
+            ColorFormat::RgbStripped => "rgb_stripped",
+            ColorFormat::RgbPercentage => "rgb_percentage",
+            ColorFormat::RgbPercentageRounded => "rgb_percentage_rounded",
+            ColorFormat::Rgba => "rgba",
+            ColorFormat::RgbaStripped => "rgba_stripped",
+            ColorFormat::RgbaPercentage => "rgba_percentage",
+            ColorFormat::RgbaPercentageRounded => "rgba_percentage_rounded",
+            ColorFormat::Xrgba => "xrgba",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ColorFormat> {
+        ColorFormat::ALL
+            .iter()
+            .find(|format| format.name() == name)
+            .copied()
+    }
+}
+
+/// `value` out of 0 - 255 as a fraction of `scale`, to three digits unless
+/// `precision` says otherwise
+fn write_fraction<W: Write + ?Sized>(
+    writer: &mut W,
+    value: u8,
+    scale: f32,
+    precision: Option<usize>,
+) -> std::fmt::Result {
+    match (precision, scale == 100.0f32) {
+        (Some(precision), _) => write!(writer, "{:.*}", precision, value as f32 * scale / 255.0f32),
+        (None, true) => write!(writer, "{}", utils::u8_to_percentage(value)),
+        (None, false) => write!(writer, "{}", utils::u8_to_f32_clamped(value)),
+    }
+}
+
+/// writes `tuple` in `format` without allocating, percentages and alpha get
+/// `precision` digits when given instead of up to three
+pub(crate) fn write_tuple<W: Write + ?Sized>(
+    writer: &mut W,
+    tuple: (u8, u8, u8, u8),
+    format: ColorFormat,
+    precision: Option<usize>,
+) -> std::fmt::Result {
+    let (red, green, blue, alpha) = tuple;
+    let channels = [red, green, blue];
+    let (prefix, suffix) = match format {
+        ColorFormat::Rgb | ColorFormat::RgbPercentage | ColorFormat::RgbPercentageRounded => {
+            ("rgb(", ")")
+        }
+        ColorFormat::Rgba | ColorFormat::RgbaPercentage | ColorFormat::RgbaPercentageRounded => {
+            ("rgba(", ")")
+        }
+        ColorFormat::Hex | ColorFormat::Hex8 => ("#", ""),
+        _ => ("", ""),
+    };
+    writer.write_str(prefix)?;
+    match format {
+        ColorFormat::Hex | ColorFormat::HexStripped => {
+            write!(writer, "{:02x}{:02x}{:02x}", red, green, blue)?
+        }
+        ColorFormat::Hex8 | ColorFormat::Hex8Stripped => {
+            write!(writer, "{:02x}{:02x}{:02x}{:02x}", red, green, blue, alpha)?
+        }
+        ColorFormat::Xrgba => write!(
+            writer,
+            "{:02x}/{:02x}/{:02x}/{:02x}",
+            red, green, blue, alpha
+        )?,
+        ColorFormat::Rgb | ColorFormat::RgbStripped => {
+            write!(writer, "{},{},{}", red, green, blue)?
+        }
+        ColorFormat::Rgba | ColorFormat::RgbaStripped => {
+            write!(writer, "{},{},{},", red, green, blue)?;
+            write_fraction(writer, alpha, 1.0f32, precision)?;
+        }
+        _ => {
+            let rounded = matches!(
+                format,
+                ColorFormat::RgbPercentageRounded | ColorFormat::RgbaPercentageRounded
+            );
+            for (index, channel) in channels.iter().enumerate() {
+                if index > 0 {
+                    writer.write_char(',')?;
+                }
+                match rounded {
+                    true => write!(writer, "{}", utils::u8_to_percentage_rounded(*channel))?,
+                    false => write_fraction(writer, *channel, 100.0f32, precision)?,
+                }
+                writer.write_char('%')?;
+            }
+            if matches!(
+                format,
+                ColorFormat::RgbaPercentage | ColorFormat::RgbaPercentageRounded
+            ) {
+                writer.write_char(',')?;
+                write_fraction(writer, alpha, 1.0f32, precision)?;
+            }
+        }
+    }
+    writer.write_str(suffix)
+}
+
+/// writes `color` in `format` to any `fmt::Write`
+pub fn write_format<W: Write + ?Sized, T: ToTuple + ?Sized>(
+    writer: &mut W,
+    color: &T,
+    format: ColorFormat,
+) -> std::fmt::Result {
+    write_tuple(writer, color.to_tuple_alpha(), format, None)
+}
+
+/// displays a color in one `ColorFormat`, a precision such as `{:.1}`
+/// applies to percentages and alpha
+#[derive(Debug, Clone, Copy)]
+pub struct FormatDisplay<'a, T: ?Sized> {
+    color: &'a T,
+    format: ColorFormat,
+}
+
+impl<'a, T: ToTuple + ?Sized> FormatDisplay<'a, T> {
+    pub fn new(color: &'a T, format: ColorFormat) -> Self {
+        FormatDisplay { color, format }
+    }
+}
+
+impl<'a, T: ToTuple + ?Sized> Display for FormatDisplay<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_tuple(f, self.color.to_tuple_alpha(), self.format, f.precision())
+    }
+}
+
+mod tests {
+    #[test]
+    fn format_tests() {
+        use super::*;
+        use crate::Color;
+        let colors = [
+            Color::from_hex("#bcfff5"),
+            Color::from_hex8("#abf78880"),
+            Color::from_hex8("#00000000"),
+        ];
+        // the adapters and the allocating methods agree on every format
+        for color in colors.iter() {
+            for format in ColorFormat::ALL.iter() {
+                let mut written = String::new();
+                write_format(&mut written, color, *format).unwrap();
+                assert_eq!(written, color.format_string(*format));
+                assert_eq!(color.display_format(*format).to_string(), written);
+                assert_eq!(ColorFormat::from_name(format.name()), Some(*format));
+            }
+        }
+        let mint = colors[1];
+        assert_eq!(mint.display_hex().to_string(), "#abf788");
+        assert_eq!(mint.display_rgba().to_string(), "rgba(171,247,136,0.502)");
+        assert_eq!(
+            format!("{:.1}", mint.display_format(ColorFormat::RgbaPercentage)),
+            "rgba(67.1%,96.9%,53.3%,0.5)"
+        );
+        assert_eq!(
+            format!("{:.0}", mint.display_format(ColorFormat::RgbPercentage)),
+            "rgb(67%,97%,53%)"
+        );
+        assert_eq!(ColorFormat::from_name("hsl"), None);
+    }
+}
@@ -0,0 +1,475 @@
+use std::error::Error;
+use std::fmt::Write;
+
+use regex::Regex;
+
+use crate::space::Hsl;
+use crate::space::Hsv;
+use crate::Color;
+
+/// what a placeholder stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Component {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    HslHue,
+    HslSaturation,
+    HslLightness,
+    HsvHue,
+    HsvSaturation,
+    HsvValue,
+}
+
+impl Component {
+    fn from_name(name: &str) -> Option<Component> {
+        let component = match name {
+            "r" => Component::Red,
+            "g" => Component::Green,
+            "b" => Component::Blue,
+            "a" => Component::Alpha,
+            "hsl.h" => Component::HslHue,
+            "hsl.s" => Component::HslSaturation,
+            "hsl.l" => Component::HslLightness,
+            "hsv.h" => Component::HsvHue,
+            "hsv.s" => Component::HsvSaturation,
+            "hsv.v" => Component::HsvValue,
+            _ => return None,
+        };
+        Some(component)
+    }
+
+    /// the largest value in the component's own unit: 255 for channels,
+    /// 360 degrees for hues and 1.0 for the rest
+    fn scale(&self) -> f32 {
+        match self {
+            Component::Red | Component::Green | Component::Blue | Component::Alpha => 255.0f32,
+            Component::HslHue | Component::HsvHue => 360.0f32,
+            _ => 1.0f32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    /// channels 0 - 255, hues in whole degrees, the rest in whole percent
+    Decimal,
+    LowerHex,
+    UpperHex,
+    /// channels and the rest 0.0 - 1.0, hues in degrees
+    Float,
+    /// 0 - 100 of the component's range
+    Percent,
+}
+
+/// the largest width or precision a placeholder takes
+const MAX_WIDTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Placeholder {
+    component: Component,
+    kind: Kind,
+    width: usize,
+    zero: bool,
+    precision: Option<usize>,
+}
+
+impl Placeholder {
+    /// `name` or `name:spec` where spec is `[0][width][.precision][kind]`
+    /// and kind is one of `d`, `x`, `X`, `f` or `p`
+    fn parse(expression: &str) -> Result<Placeholder, String> {
+        let (name, spec) = expression.split_once(':').unwrap_or((expression, ""));
+        let component = Component::from_name(name.trim())
+            .ok_or_else(|| format!("unknown component `{}`", name.trim()))?;
+        let (kind, spec) = match spec.chars().last() {
+            Some('d') => (Kind::Decimal, &spec[..spec.len() - 1]),
+            Some('x') => (Kind::LowerHex, &spec[..spec.len() - 1]),
+            Some('X') => (Kind::UpperHex, &spec[..spec.len() - 1]),
+            Some('f') => (Kind::Float, &spec[..spec.len() - 1]),
+            Some('p') => (Kind::Percent, &spec[..spec.len() - 1]),
+            _ => (Kind::Decimal, spec),
+        };
+        let (width, precision) = spec.split_once('.').unwrap_or((spec, ""));
+        let invalid = || format!("invalid format `{}`", expression);
+        let zero = width.starts_with('0');
+        let bounded = |digits: &str| match digits.parse() {
+            Ok(value) if value <= MAX_WIDTH => Ok(value),
+            _ => Err(invalid()),
+        };
+        let width = match width {
+            "" => 0,
+            _ => bounded(width)?,
+        };
+        let precision = match (spec.contains('.'), precision) {
+            (false, _) => None,
+            (true, "") => return Err(invalid()),
+            (true, precision) => Some(bounded(precision)?),
+        };
+        let hex = matches!(kind, Kind::LowerHex | Kind::UpperHex);
+        if hex && component.scale() != 255.0f32 {
+            return Err(format!("`{}` can not be written as hex", name.trim()));
+        }
+        if precision.is_some() && !matches!(kind, Kind::Float | Kind::Percent) {
+            return Err(format!(
+                "only `f` and `p` take a precision in `{}`",
+                expression
+            ));
+        }
+        Ok(Placeholder {
+            component,
+            kind,
+            width,
+            zero,
+            precision,
+        })
+    }
+
+    fn write<W: Write + ?Sized>(&self, writer: &mut W, value: f32) -> std::fmt::Result {
+        let scale = self.component.scale();
+        let mut text = String::new();
+        match self.kind {
+            Kind::Decimal => match scale {
+                255.0 => write!(text, "{}", value as u8)?,
+                360.0 => write!(text, "{}", value.round() as u32 % 360)?,
+                _ => write!(text, "{}", (value * 100.0f32).round() as u32)?,
+            },
+            Kind::LowerHex => write!(text, "{:02x}", value as u8)?,
+            Kind::UpperHex => write!(text, "{:02X}", value as u8)?,
+            Kind::Float | Kind::Percent => {
+                let number = match (self.kind, scale) {
+                    (Kind::Float, 255.0) => value / 255.0f32,
+                    (Kind::Float, _) => value,
+                    (_, _) => value * 100.0f32 / scale,
+                };
+                match self.precision {
+                    Some(precision) => write!(text, "{:.*}", precision, number)?,
+                    None => write!(text, "{}", (number * 1000.0f32).round() / 1000.0f32)?,
+                }
+            }
+        }
+        match self.zero {
+            true => write!(writer, "{:0>width$}", text, width = self.width),
+            false => write!(writer, "{:>width$}", text, width = self.width),
+        }
+    }
+
+    /// what `write` produces, as a regex capture group
+    fn regex(&self) -> String {
+        match self.kind {
+            Kind::Decimal => r" *(\d+)".to_string(),
+            // always two digits, padded with zeros or spaces up to the width
+            Kind::LowerHex | Kind::UpperHex => match self.zero {
+                true => format!("([0-9a-fA-F]{{{}}})", self.width.max(2)),
+                false => r" *([0-9a-fA-F]{2})".to_string(),
+            },
+            Kind::Float | Kind::Percent => r" *(\d+(?:\.\d+)?)".to_string(),
+        }
+    }
+
+    /// inverse of `write`, in the component's own unit
+    fn read(&self, text: &str) -> Result<f32, String> {
+        let scale = self.component.scale();
+        let invalid = || format!("invalid value `{}`", text);
+        let value = match self.kind {
+            Kind::LowerHex | Kind::UpperHex => {
+                u8::from_str_radix(text, 16).map_err(|_| invalid())? as f32
+            }
+            _ => {
+                let number: f32 = text.parse().map_err(|_| invalid())?;
+                match (self.kind, scale) {
+                    (Kind::Decimal, 255.0) | (Kind::Decimal, 360.0) => number,
+                    (Kind::Decimal, _) => number / 100.0f32,
+                    (Kind::Float, 255.0) => number * 255.0f32,
+                    (Kind::Float, _) => number,
+                    (_, _) => number * scale / 100.0f32,
+                }
+            }
+        };
+        if value > scale + 1e-3f32 {
+            return Err(format!("`{}` is out of range", text));
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// a compiled output pattern such as `0x{a:X}{r:X}{g:X}{b:X}`,
+/// `Color({r:.3f}f, {g:.3f}f, {b:.3f}f)` or `{hsl.h}deg {hsl.s:.1p}%`
+///
+/// a placeholder is `{component}` or `{component:spec}`, components are
+/// `r`, `g`, `b`, `a`, `hsl.h`, `hsl.s`, `hsl.l`, `hsv.h`, `hsv.s` and `hsv.v`,
+/// spec is `[0][width][.precision][kind]` with kind
+/// - `d`, the default: channels 0 - 255, hues in degrees, the rest in percent
+/// - `x` and `X`: two hex digits, channels only
+/// - `f`: channels and the rest 0.0 - 1.0, hues in degrees
+/// - `p`: percent of the range, hues included
+///
+/// width and precision go up to 64, floats without a precision get up to
+/// three digits, `{{` and `}}` are literal braces
+#[derive(Debug, Clone)]
+pub struct ColorFormatter {
+    segments: Vec<Segment>,
+    regex: Regex,
+}
+
+impl ColorFormatter {
+    pub fn new(pattern: &str) -> Result<ColorFormatter, Box<dyn Error>> {
+        let error_at = |position: usize, message: &str| -> Box<dyn Error> {
+            format!(
+                "{} at column {}",
+                message,
+                pattern[..position].chars().count() + 1
+            )
+            .into()
+        };
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut index = 0usize;
+        while let Some(offset) = pattern[index..].find(['{', '}']) {
+            let start = index + offset;
+            literal.push_str(&pattern[index..start]);
+            let rest = &pattern[start..];
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                literal.push_str(&rest[..1]);
+                index = start + 2;
+                continue;
+            }
+            if rest.starts_with('}') {
+                return Err(error_at(start, "unmatched `}`"));
+            }
+            let end = match rest[1..].find(['{', '}']) {
+                Some(end) if rest[1 + end..].starts_with('}') => start + 1 + end,
+                _ => return Err(error_at(start, "unclosed `{`")),
+            };
+            let placeholder = Placeholder::parse(&pattern[start + 1..end])
+                .map_err(|message| error_at(start, &message))?;
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(std::mem::take(&mut literal)));
+            }
+            segments.push(Segment::Placeholder(placeholder));
+            index = end + 1;
+        }
+        literal.push_str(&pattern[index..]);
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        let mut expression = "^".to_string();
+        for segment in segments.iter() {
+            match segment {
+                Segment::Literal(text) => expression.push_str(&regex::escape(text)),
+                Segment::Placeholder(placeholder) => expression.push_str(&placeholder.regex()),
+            }
+        }
+        expression.push('$');
+        Ok(ColorFormatter {
+            segments,
+            regex: Regex::new(&expression)?,
+        })
+    }
+
+    pub fn write<W: Write + ?Sized>(&self, writer: &mut W, color: &Color) -> std::fmt::Result {
+        let hsl = Hsl::from(*color);
+        let hsv = Hsv::from(*color);
+        for segment in self.segments.iter() {
+            let placeholder = match segment {
+                Segment::Literal(text) => {
+                    writer.write_str(text)?;
+                    continue;
+                }
+                Segment::Placeholder(placeholder) => placeholder,
+            };
+            let value = match placeholder.component {
+                Component::Red => color.red as f32,
+                Component::Green => color.green as f32,
+                Component::Blue => color.blue as f32,
+                Component::Alpha => color.alpha as f32,
+                Component::HslHue => hsl.h,
+                Component::HslSaturation => hsl.s,
+                Component::HslLightness => hsl.l,
+                Component::HsvHue => hsv.h,
+                Component::HsvSaturation => hsv.s,
+                Component::HsvValue => hsv.v,
+            };
+            placeholder.write(writer, value)?;
+        }
+        Ok(())
+    }
+
+    pub fn format(&self, color: &Color) -> String {
+        let mut text = String::new();
+        self.write(&mut text, color).unwrap();
+        text
+    }
+
+    /// reads back what `format` wrote, the pattern needs all of `r`, `g` and
+    /// `b`, of `hsl.*` or of `hsv.*`, alpha is 255 unless `a` is there
+    /// when a component shows up more than once the first one counts
+    pub fn parse(&self, s: &str) -> Result<Color, Box<dyn Error>> {
+        let capture = self
+            .regex
+            .captures(s)
+            .ok_or_else(|| format!("`{}` does not match the pattern", s))?;
+        let mut values: Vec<(Component, f32)> = Vec::new();
+        let placeholders = self.segments.iter().filter_map(|segment| match segment {
+            Segment::Placeholder(placeholder) => Some(placeholder),
+            Segment::Literal(_) => None,
+        });
+        for (index, placeholder) in placeholders.enumerate() {
+            let value = placeholder.read(&capture[index + 1])?;
+            if values
+                .iter()
+                .all(|(component, _)| *component != placeholder.component)
+            {
+                values.push((placeholder.component, value));
+            }
+        }
+        let get = |component: Component| {
+            values
+                .iter()
+                .find(|(found, _)| *found == component)
+                .map(|(_, value)| *value)
+        };
+        let channel = |value: f32| value.round().clamp(0.0f32, 255.0f32) as u8;
+        let alpha = get(Component::Alpha).map_or(255, channel);
+        let rgb = (
+            get(Component::Red),
+            get(Component::Green),
+            get(Component::Blue),
+        );
+        let hsl = (
+            get(Component::HslHue),
+            get(Component::HslSaturation),
+            get(Component::HslLightness),
+        );
+        let hsv = (
+            get(Component::HsvHue),
+            get(Component::HsvSaturation),
+            get(Component::HsvValue),
+        );
+        let color = match (rgb, hsl, hsv) {
+            ((Some(red), Some(green), Some(blue)), _, _) => {
+                Color::rgb(channel(red), channel(green), channel(blue))
+            }
+            (_, (Some(h), Some(s), Some(l)), _) => Color::from(Hsl::new(h, s, l)),
+            (_, _, (Some(h), Some(s), Some(v))) => Color::from(Hsv::new(h, s, v)),
+            _ => return Err("Pattern does not hold a whole color to read back".into()),
+        };
+        Ok(color.with_alpha(alpha))
+    }
+}
+
+mod tests {
+    #[test]
+    fn format_tests() {
+        use super::*;
+        let color = Color::rgba(255, 170, 187, 204);
+        let format = |pattern: &str| ColorFormatter::new(pattern).unwrap().format(&color);
+        assert_eq!(format("0x{a:X}{r:X}{g:X}{b:X}"), "0xCCFFAABB");
+        assert_eq!(
+            format("Color({r:.1f}f, {g:.1f}f, {b:.1f}f)"),
+            "Color(1.0f, 0.7f, 0.7f)"
+        );
+        assert_eq!(format("{r:3} {g:3} {b:3}"), "255 170 187");
+        assert_eq!(format("RGB({r},{g},{b})"), "RGB(255,170,187)");
+        assert_eq!(format("{a:f} {a:p} {a:.0p}% {r:x}"), "0.8 80 80% ff");
+        assert_eq!(
+            format("hsl({hsl.h}, {hsl.s}%, {hsl.l}%)"),
+            "hsl(348, 100%, 83%)"
+        );
+        assert_eq!(
+            format("{hsv.h:.2f} {hsv.s:.3f} {hsv.v:p}"),
+            "348.00 0.333 100"
+        );
+        assert_eq!(format("{{{r:04}}}"), "{0255}");
+        assert_eq!(format("{r:5}|{g:05}|{b:5x}"), "  255|00170|   bb");
+
+        for bad in [
+            "{r",
+            "r}",
+            "{red}",
+            "{hsl.h:x}",
+            "{r:.2d}",
+            "{r:.f}",
+            "{r:q}",
+            "{r:65}",
+            "{r:99999999999}",
+            "{a:.65f}",
+        ]
+        .iter()
+        {
+            assert!(ColorFormatter::new(bad).is_err(), "{}", bad);
+        }
+        assert_eq!(format("{r:064}").len(), 64);
+        assert!(ColorFormatter::new("{a:.64f}").is_ok());
+        assert_eq!(
+            ColorFormatter::new("#{r:x}{g}}").unwrap_err().to_string(),
+            "unmatched `}` at column 10"
+        );
+    }
+
+    #[test]
+    fn parse_tests() {
+        use super::*;
+        let color = Color::rgba(255, 170, 187, 204);
+        for pattern in [
+            "0x{a:X}{r:X}{g:X}{b:X}",
+            "{r:3} {g:3} {b:3} {a:3}",
+            "RGB({r},{g},{b},{a})",
+            "Color({r:.5f}f, {g:.5f}f, {b:.5f}f, {a:.5f}f)",
+            "{r:p}% {g:p}% {b:p}% {a:p}%",
+            "{r:4x}{g:4x}{b:4x}{a:4x}",
+            "{r:04X}|{g:5x}|{b:X}|{a:06x}",
+        ]
+        .iter()
+        {
+            let formatter = ColorFormatter::new(pattern).unwrap();
+            assert_eq!(
+                formatter.parse(&formatter.format(&color)).unwrap(),
+                color,
+                "{}",
+                pattern
+            );
+        }
+        let opaque = color.with_alpha(255);
+        for pattern in [
+            "hsl({hsl.h:.2f}, {hsl.s:.3p}%, {hsl.l:.3p}%)",
+            "{hsv.h:.3f} {hsv.s:.4f} {hsv.v:.4f}",
+        ]
+        .iter()
+        {
+            let formatter = ColorFormatter::new(pattern).unwrap();
+            assert_eq!(
+                formatter.parse(&formatter.format(&opaque)).unwrap(),
+                opaque,
+                "{}",
+                pattern
+            );
+        }
+
+        let padded = ColorFormatter::new("{r:5}|{g:05}|{b:5x}").unwrap();
+        assert_eq!(
+            padded.parse("  255|00170|   bb").unwrap(),
+            Color::rgb(255, 170, 187)
+        );
+
+        let formatter = ColorFormatter::new("RGB({r},{g},{b})").unwrap();
+        assert_eq!(
+            formatter.parse("RGB(0,  128,9)").unwrap(),
+            Color::rgb(0, 128, 9)
+        );
+        assert!(formatter.parse("RGB(0,128,9").is_err());
+        assert!(formatter.parse("RGB(0,256,9)").is_err());
+        assert!(formatter.parse("RGB(0,1.5,9)").is_err());
+        assert!(ColorFormatter::new("{r} {g}")
+            .unwrap()
+            .parse("1 2")
+            .is_err());
+    }
+}
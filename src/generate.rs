@@ -0,0 +1,260 @@
+use crate::contrast::best_text_color;
+use crate::contrast::ContrastMetric;
+use crate::quantize::quantize;
+use crate::quantize::Method;
+use crate::quantize::Swatch;
+use crate::scheme::TerminalScheme;
+use crate::space::Oklch;
+use crate::traits::*;
+use crate::Color;
+
+/// oklch hue targets for red, green, yellow, blue, magenta and cyan (color1 - color6)
+const ANSI_HUES: [f32; 6] = [25.0, 145.0, 95.0, 260.0, 330.0, 200.0];
+
+/// how far an ansi hue may drift towards the wallpaper from its target
+const HUE_TOLERANCE: f32 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Variant {
+    Dark,
+    Light,
+}
+
+/// knobs for `generate`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeneratorOptions {
+    pub variant: Variant,
+    /// multiplier on the chroma of the ansi colors, 1.0 keeps the wallpaper's
+    pub saturation: f32,
+    /// minimum wcag ratio of the foreground and color1 - color15 against the background
+    pub min_contrast: f32,
+    /// seed for the k-means palette extraction
+    pub seed: u64,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            variant: Variant::Dark,
+            saturation: 1.0,
+            min_contrast: 4.5,
+            seed: 0,
+        }
+    }
+}
+
+/// signed difference `to - from` in degrees, -180.0 - 180.0
+fn hue_difference(from: f32, to: f32) -> f32 {
+    (to - from + 180.0f32).rem_euclid(360.0f32) - 180.0f32
+}
+
+fn lch_color(l: f32, c: f32, h: f32) -> Color {
+    Color::from(Oklch::new(l.clamp(0.0f32, 1.0f32), c.max(0.0f32), h).clamp_chroma())
+}
+
+/// `color` moved in lightness until it reaches `target` against `background`,
+/// falling back to black or white when no lightness does
+fn with_contrast(color: Color, background: Color, target: f32) -> Color {
+    color
+        .ensure_contrast(background, target, ContrastMetric::Wcag)
+        .or_else(|| {
+            best_text_color(
+                background,
+                &[Color::from_hex("#000000"), Color::from_hex("#ffffff")],
+                ContrastMetric::Wcag,
+            )
+        })
+        .unwrap()
+}
+
+/// the swatch nearest in hue to `target`, ignoring near grays
+fn closest_swatch(swatches: &[(Oklch, usize)], target: f32) -> Option<Oklch> {
+    swatches
+        .iter()
+        .filter(|(lch, _)| lch.c > 0.02f32)
+        .map(|(lch, _)| *lch)
+        .min_by(|first, second| {
+            hue_difference(target, first.h)
+                .abs()
+                .partial_cmp(&hue_difference(target, second.h).abs())
+                .unwrap()
+        })
+}
+
+/// a pywal style terminal scheme from the pixels of a wallpaper
+/// background and foreground take the hue of the dominant color, color1 -
+/// color6 keep the red, green, yellow, blue, magenta, cyan order with hues at
+/// most 20 degrees from their usual place, color9 - color14 are their bright
+/// versions, color0 / color8 / color7 / color15 are tinted grays
+/// `None` when there are no opaque pixels
+pub fn generate(pixels: &[Color], options: &GeneratorOptions) -> Option<TerminalScheme> {
+    let swatches: Vec<(Oklch, usize)> = quantize(
+        pixels,
+        16,
+        Method::KMeans {
+            seed: options.seed,
+            iterations: 32,
+        },
+    )
+    .iter()
+    .map(|Swatch { color, population }| (Oklch::from(*color), *population))
+    .collect();
+    let (dominant, _) = *swatches.first()?;
+    let total: usize = swatches.iter().map(|(_, population)| population).sum();
+    let mean_chroma = swatches
+        .iter()
+        .map(|(lch, population)| lch.c * *population as f32)
+        .sum::<f32>()
+        / total as f32;
+
+    let dark = options.variant == Variant::Dark;
+    let (background_l, foreground_l, ansi_l, bright_shift) = match dark {
+        true => (0.18f32, 0.92f32, 0.70f32, 0.08f32),
+        false => (0.97f32, 0.25f32, 0.50f32, -0.08f32),
+    };
+    let tint = dominant.c.min(0.03f32);
+    let hue = dominant.h;
+    let background = lch_color(background_l, tint, hue);
+    let foreground = with_contrast(
+        lch_color(foreground_l, tint / 2.0f32, hue),
+        background,
+        options.min_contrast,
+    );
+
+    let mut colors = [Color::new(); 16];
+    colors[0] = lch_color(
+        background_l + (foreground_l - background_l) * 0.1f32,
+        tint,
+        hue,
+    );
+    colors[8] = with_contrast(
+        lch_color(
+            background_l + (foreground_l - background_l) * 0.45f32,
+            tint,
+            hue,
+        ),
+        background,
+        options.min_contrast,
+    );
+    colors[7] = with_contrast(
+        lch_color(
+            background_l + (foreground_l - background_l) * 0.85f32,
+            tint,
+            hue,
+        ),
+        background,
+        options.min_contrast,
+    );
+    colors[15] = foreground;
+
+    for (slot, target) in ANSI_HUES.iter().enumerate() {
+        let (h, c) = match closest_swatch(&swatches, *target) {
+            Some(lch) => {
+                let drift = hue_difference(*target, lch.h).clamp(-HUE_TOLERANCE, HUE_TOLERANCE);
+                (target + drift, lch.c.max(mean_chroma))
+            }
+            None => (*target, mean_chroma),
+        };
+        let c = c.max(0.08f32) * options.saturation;
+        colors[slot + 1] = with_contrast(lch_color(ansi_l, c, h), background, options.min_contrast);
+        colors[slot + 9] = with_contrast(
+            lch_color(ansi_l + bright_shift, c * 1.1f32, h),
+            background,
+            options.min_contrast,
+        );
+    }
+
+    Some(TerminalScheme {
+        background,
+        foreground,
+        cursor: foreground,
+        colors,
+    })
+}
+
+mod tests {
+    #[allow(dead_code)]
+    fn wallpaper() -> Vec<crate::Color> {
+        use crate::traits::*;
+        let mut pixels = Vec::new();
+        for (hex, count) in [
+            ("#1b3a4b", 4000),
+            ("#356c80", 1500),
+            ("#e0a458", 600),
+            ("#c94f3d", 300),
+            ("#8fb996", 400),
+            ("#f2e8cf", 200),
+        ]
+        .iter()
+        {
+            let color = crate::Color::from_hex(hex);
+            for offset in 0..*count {
+                let jitter = (offset % 5) as u8;
+                let (red, green, blue) = color.to_tuple();
+                pixels.push(crate::Color::from_tuple((
+                    red.saturating_add(jitter),
+                    green,
+                    blue.saturating_sub(jitter),
+                )));
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn generate_tests() {
+        use super::*;
+        use crate::contrast::contrast_ratio;
+        let pixels = wallpaper();
+        for variant in [Variant::Dark, Variant::Light].iter() {
+            let options = GeneratorOptions {
+                variant: *variant,
+                ..GeneratorOptions::default()
+            };
+            let scheme = generate(&pixels, &options).unwrap();
+            assert_eq!(scheme, generate(&pixels, &options).unwrap());
+            assert!(contrast_ratio(scheme.foreground, scheme.background) >= 4.5f32);
+            for color in scheme.colors[1..].iter() {
+                assert!(contrast_ratio(*color, scheme.background) >= 4.5f32);
+            }
+            let background = scheme.background.relative_luminance();
+            let foreground = scheme.foreground.relative_luminance();
+            match variant {
+                Variant::Dark => assert!(background < foreground),
+                Variant::Light => assert!(background > foreground),
+            }
+            for (slot, target) in ANSI_HUES.iter().enumerate() {
+                for color in [scheme.colors[slot + 1], scheme.colors[slot + 9]].iter() {
+                    let hue = Oklch::from(*color).h;
+                    assert!(
+                        hue_difference(*target, hue).abs() <= HUE_TOLERANCE + 3.0f32,
+                        "{:?} slot {}: {} vs {}",
+                        variant,
+                        slot + 1,
+                        hue,
+                        target
+                    );
+                }
+            }
+        }
+    }
+    #[test]
+    fn saturation_tests() {
+        use super::*;
+        let pixels = wallpaper();
+        let chroma = |saturation: f32| {
+            let options = GeneratorOptions {
+                saturation,
+                ..GeneratorOptions::default()
+            };
+            let scheme = generate(&pixels, &options).unwrap();
+            scheme.colors[1..7]
+                .iter()
+                .map(|color| Oklch::from(*color).c)
+                .sum::<f32>()
+        };
+        assert!(chroma(0.5f32) < chroma(1.0f32));
+        assert!(chroma(0.0f32) < 0.01f32);
+        assert_eq!(generate(&[], &GeneratorOptions::default()), None);
+    }
+}
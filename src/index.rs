@@ -0,0 +1,364 @@
+use crate::difference::Cie94Application;
+use crate::difference::DeltaE;
+use crate::space::Lab;
+use crate::space::Oklab;
+use crate::Color;
+
+/// a palette entry returned by a `PaletteIndex` query
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Neighbor {
+    /// position in the slice the index was built from
+    pub index: usize,
+    pub color: Color,
+    pub distance: f32,
+}
+
+struct Node {
+    point: [f32; 3],
+    index: usize,
+    axis: usize,
+}
+
+/// nearest color lookup against a fixed palette
+/// a k-d tree over oklab for `DeltaE::Oklab` and over cielab for every other
+/// metric, results are exact for every metric but the tree prunes far less for
+/// `Ciede2000` than for the others
+/// the query color is the reference for the asymmetric metrics
+pub struct PaletteIndex {
+    colors: Vec<Color>,
+    metric: DeltaE,
+    nodes: Vec<Node>,
+}
+
+/// a query point and how far the metric can shrink a euclidean distance from it
+struct Query {
+    point: [f32; 3],
+    metric: DeltaE,
+    scale: f32,
+    chroma: f32,
+}
+
+impl Query {
+    fn new(metric: DeltaE, point: [f32; 3]) -> Self {
+        let chroma = (point[1] * point[1] + point[2] * point[2]).sqrt();
+        // the weighting functions only depend on the reference for cie94 and cmc,
+        // sh never exceeds sc for either and is not weighted, so the scale has to
+        // cover sc on its own as well when the chroma weight is below one
+        let scale = match metric {
+            DeltaE::Cie94(application) => {
+                let (k_l, k_1) = match application {
+                    Cie94Application::GraphicArts => (1.0f32, 0.045f32),
+                    Cie94Application::Textiles => (2.0f32, 0.048f32),
+                };
+                1.0f32 / k_l.max(1.0f32 + k_1 * chroma)
+            }
+            DeltaE::Cmc {
+                lightness,
+                chroma: chroma_weight,
+            } => {
+                let s_l = match point[0] < 16.0f32 {
+                    true => 0.511f32,
+                    false => 0.040975f32 * point[0] / (1.0f32 + 0.01765f32 * point[0]),
+                };
+                let s_c = 0.0638f32 * chroma / (1.0f32 + 0.0131f32 * chroma) + 0.638f32;
+                1.0f32 / (lightness * s_l).max(chroma_weight * s_c).max(s_c)
+            }
+            _ => 1.0f32,
+        };
+        Query {
+            point,
+            metric,
+            scale,
+            chroma,
+        }
+    }
+
+    fn euclidean(&self) -> bool {
+        matches!(self.metric, DeltaE::Cie76 | DeltaE::Oklab)
+    }
+
+    /// metric distance, squared for the euclidean metrics to skip the square root
+    fn score(&self, point: &[f32; 3]) -> f32 {
+        match self.metric {
+            DeltaE::Cie76 | DeltaE::Oklab => {
+                (point[0] - self.point[0]).powi(2)
+                    + (point[1] - self.point[1]).powi(2)
+                    + (point[2] - self.point[2]).powi(2)
+            }
+            _ => self.metric.between(
+                Lab::new(self.point[0], self.point[1], self.point[2]),
+                Lab::new(point[0], point[1], point[2]),
+            ),
+        }
+    }
+
+    /// smallest score of any point at least `euclidean` away
+    fn lower_bound(&self, euclidean: f32) -> f32 {
+        match self.metric {
+            DeltaE::Cie76 | DeltaE::Oklab => euclidean * euclidean,
+            DeltaE::Ciede2000 => {
+                // sl peaks at 1.747, sc grows with the mean chroma which is at most
+                // 1.5 (query chroma + distance / 2) after the a* rescaling, and the
+                // rotation term removes at most 87% of the chroma and hue share
+                let s_c = 1.0f32 + 0.0675f32 * (self.chroma + euclidean / 2.0f32);
+                euclidean * (1.0f32 / 1.747f32).min(0.36f32 / s_c)
+            }
+            _ => euclidean * self.scale,
+        }
+    }
+}
+
+impl PaletteIndex {
+    pub fn new(colors: &[Color], metric: DeltaE) -> Self {
+        let mut nodes: Vec<Node> = colors
+            .iter()
+            .enumerate()
+            .map(|(index, color)| Node {
+                point: Self::point(metric, *color),
+                index,
+                axis: 0,
+            })
+            .collect();
+        Self::build(&mut nodes);
+        PaletteIndex {
+            colors: colors.to_vec(),
+            metric,
+            nodes,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    pub fn metric(&self) -> DeltaE {
+        self.metric
+    }
+
+    /// the palette entry closest to `color`, `None` for an empty palette
+    pub fn nearest(&self, color: Color) -> Option<Neighbor> {
+        self.k_nearest(color, 1).into_iter().next()
+    }
+
+    /// the `k` palette entries closest to `color`, closest first
+    pub fn k_nearest(&self, color: Color, k: usize) -> Vec<Neighbor> {
+        if k == 0 || self.nodes.is_empty() {
+            return Vec::new();
+        }
+        let query = Query::new(self.metric, Self::point(self.metric, color));
+        let mut best: Vec<(f32, usize)> = Vec::with_capacity(k + 1);
+        self.search(0, self.nodes.len(), &query, k, &mut best);
+        best.into_iter()
+            .map(|(score, position)| {
+                let index = self.nodes[position].index;
+                Neighbor {
+                    index,
+                    color: self.colors[index],
+                    distance: match query.euclidean() {
+                        true => score.sqrt(),
+                        false => score,
+                    },
+                }
+            })
+            .collect()
+    }
+
+    fn point(metric: DeltaE, color: Color) -> [f32; 3] {
+        match metric {
+            DeltaE::Oklab => {
+                let oklab = Oklab::from(color);
+                [oklab.l, oklab.a, oklab.b]
+            }
+            _ => {
+                let lab = Lab::from(color);
+                [lab.l, lab.a, lab.b]
+            }
+        }
+    }
+
+    /// orders `nodes` so every subrange has its median, split on the widest axis, in the middle
+    fn build(nodes: &mut [Node]) {
+        if nodes.is_empty() {
+            return;
+        }
+        let mut axis = 0;
+        let mut widest = -1.0f32;
+        for candidate in 0..3 {
+            let (low, high) = nodes
+                .iter()
+                .fold((f32::MAX, f32::MIN), |(low, high), node| {
+                    (
+                        low.min(node.point[candidate]),
+                        high.max(node.point[candidate]),
+                    )
+                });
+            if high - low > widest {
+                widest = high - low;
+                axis = candidate;
+            }
+        }
+        let middle = nodes.len() / 2;
+        nodes.select_nth_unstable_by(middle, |first, second| {
+            first.point[axis].partial_cmp(&second.point[axis]).unwrap()
+        });
+        nodes[middle].axis = axis;
+        let (left, right) = nodes.split_at_mut(middle);
+        Self::build(left);
+        Self::build(&mut right[1..]);
+    }
+
+    /// `best` holds (score, node position) sorted closest first
+    fn search(
+        &self,
+        low: usize,
+        high: usize,
+        query: &Query,
+        k: usize,
+        best: &mut Vec<(f32, usize)>,
+    ) {
+        if low >= high {
+            return;
+        }
+        let middle = (low + high) / 2;
+        let node = &self.nodes[middle];
+        let score = query.score(&node.point);
+        if best.len() < k || score < best[best.len() - 1].0 {
+            let position = best
+                .iter()
+                .position(|(other, _)| score < *other)
+                .unwrap_or(best.len());
+            best.insert(position, (score, middle));
+            best.truncate(k);
+        }
+
+        let difference = query.point[node.axis] - node.point[node.axis];
+        let (near, far) = match difference < 0.0f32 {
+            true => ((low, middle), (middle + 1, high)),
+            false => ((middle + 1, high), (low, middle)),
+        };
+        self.search(near.0, near.1, query, k, best);
+        if best.len() < k || query.lower_bound(difference.abs()) < best[best.len() - 1].0 {
+            self.search(far.0, far.1, query, k, best);
+        }
+    }
+}
+
+mod tests {
+    #[test]
+    fn nearest_tests() {
+        use super::*;
+        use crate::traits::*;
+
+        let mut palette = Vec::new();
+        for red in (0..=255).step_by(51) {
+            for green in (0..=255).step_by(85) {
+                for blue in (0..=255).step_by(64) {
+                    palette.push(Color::from_tuple((red, green, blue)));
+                }
+            }
+        }
+        let metrics = [
+            DeltaE::Cie76,
+            DeltaE::Oklab,
+            DeltaE::Ciede2000,
+            DeltaE::Cie94(Cie94Application::GraphicArts),
+            DeltaE::CMC_ACCEPTABILITY,
+        ];
+        for metric in metrics.iter() {
+            let index = PaletteIndex::new(&palette, *metric);
+            assert_eq!(index.len(), palette.len());
+            for query in (0..=255u32).step_by(7) {
+                let color =
+                    Color::from_tuple((query as u8, (query * 3 % 256) as u8, (255 - query) as u8));
+                let mut brute: Vec<(f32, usize)> = palette
+                    .iter()
+                    .enumerate()
+                    .map(|(position, entry)| (color.distance(*entry, *metric), position))
+                    .collect();
+                brute.sort_by(|first, second| first.0.partial_cmp(&second.0).unwrap());
+
+                let found = index.k_nearest(color, 3);
+                assert_eq!(found.len(), 3);
+                assert!(found[0].distance <= found[1].distance);
+                for (neighbor, expected) in found.iter().zip(brute.iter()) {
+                    assert!(
+                        (neighbor.distance - expected.0).abs() < 1e-3,
+                        "{:?} {:?}: {} vs {}",
+                        metric,
+                        color,
+                        neighbor.distance,
+                        expected.0
+                    );
+                }
+                let nearest = index.nearest(color).unwrap();
+                assert_eq!(nearest.color, palette[nearest.index]);
+                assert!((nearest.distance - brute[0].0).abs() < 1e-3);
+            }
+        }
+    }
+    #[test]
+    fn random_tests() {
+        use super::*;
+        use crate::traits::*;
+
+        // random palettes and queries against brute force, with weights below
+        // one where the tree's bound is tightest
+        let mut random = crate::quantize::SplitMix64::new(11);
+        let mut next_color = || {
+            let value = random.next_u64();
+            Color::from_tuple((value as u8, (value >> 8) as u8, (value >> 16) as u8))
+        };
+        let palette: Vec<Color> = (0..300).map(|_| next_color()).collect();
+        let queries: Vec<Color> = (0..2000).map(|_| next_color()).collect();
+        let metrics = [
+            DeltaE::Cmc {
+                lightness: 1.0,
+                chroma: 0.5,
+            },
+            DeltaE::Cmc {
+                lightness: 0.5,
+                chroma: 0.5,
+            },
+            DeltaE::CMC_PERCEPTIBILITY,
+            DeltaE::Cie94(Cie94Application::Textiles),
+            DeltaE::Ciede2000,
+        ];
+        for metric in metrics.iter() {
+            let index = PaletteIndex::new(&palette, *metric);
+            for color in queries.iter() {
+                let brute = palette
+                    .iter()
+                    .map(|entry| color.distance(*entry, *metric))
+                    .fold(f32::INFINITY, f32::min);
+                let nearest = index.nearest(*color).unwrap();
+                assert!(
+                    (nearest.distance - brute).abs() < 1e-3,
+                    "{:?} {:?}: {} vs {}",
+                    metric,
+                    color,
+                    nearest.distance,
+                    brute
+                );
+            }
+        }
+    }
+    #[test]
+    fn edge_tests() {
+        use super::*;
+        use crate::traits::*;
+        let empty = PaletteIndex::new(&[], DeltaE::Oklab);
+        assert!(empty.is_empty());
+        assert_eq!(empty.nearest(Color::new()), None);
+
+        let palette = [Color::from_hex("#ff0000"), Color::from_hex("#0000ff")];
+        let index = PaletteIndex::new(&palette, DeltaE::Oklab);
+        assert_eq!(index.k_nearest(Color::new(), 5).len(), 2);
+        assert_eq!(index.k_nearest(Color::new(), 0).len(), 0);
+        let nearest = index.nearest(Color::from_hex("#ee1111")).unwrap();
+        assert_eq!(nearest.index, 0);
+    }
+}
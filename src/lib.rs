@@ -1,5 +1,27 @@
 pub mod traits;
 pub mod utils;
 pub mod color;
+pub mod ansi;
+pub mod base16;
+pub mod bulk;
+#[cfg(feature = "bytemuck")]
+pub mod cast;
+pub mod codegen;
+pub mod contrast;
+pub mod cvd;
+pub mod difference;
+pub mod emulator;
+pub mod format;
+pub mod formatter;
+pub mod generate;
+pub mod index;
+pub mod palette;
+pub mod pixel;
+pub mod quantize;
+pub mod scheme;
+pub mod serde;
+pub mod space;
+pub mod template;
+pub mod terminal;
 
-pub use color::Color;
\ No newline at end of file
+pub use color::Color;
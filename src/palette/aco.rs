@@ -0,0 +1,183 @@
+use std::convert::TryFrom;
+use std::error::Error;
+
+use crate::palette::cmyk_to_color;
+use crate::palette::push_utf16;
+use crate::palette::unit_to_u8;
+use crate::palette::utf16_units;
+use crate::palette::Palette;
+use crate::palette::Reader;
+use crate::space::Lab;
+use crate::traits::*;
+use crate::Color;
+
+/// hsb with every component 0.0 - 1.0
+fn hsb_to_color(hue: f32, saturation: f32, brightness: f32) -> Color {
+    let sector = (hue * 6.0f32).rem_euclid(6.0f32);
+    let chroma = brightness * saturation;
+    let second = chroma * (1.0f32 - (sector % 2.0f32 - 1.0f32).abs());
+    let (red, green, blue) = match sector as u8 {
+        0 => (chroma, second, 0.0f32),
+        1 => (second, chroma, 0.0f32),
+        2 => (0.0f32, chroma, second),
+        3 => (0.0f32, second, chroma),
+        4 => (second, 0.0f32, chroma),
+        _ => (chroma, 0.0f32, second),
+    };
+    let offset = brightness - chroma;
+    Color::from_tuple((
+        unit_to_u8(red + offset),
+        unit_to_u8(green + offset),
+        unit_to_u8(blue + offset),
+    ))
+}
+
+/// one color record, components as photoshop stores them
+fn aco_color(space: u16, values: [u16; 4]) -> Result<Color, Box<dyn Error>> {
+    let unit = |value: u16| value as f32 / 65535.0f32;
+    let color = match space {
+        0 => Color::from_tuple((
+            ((values[0] as u32 + 128) / 257) as u8,
+            ((values[1] as u32 + 128) / 257) as u8,
+            ((values[2] as u32 + 128) / 257) as u8,
+        )),
+        1 => hsb_to_color(unit(values[0]), unit(values[1]), unit(values[2])),
+        // 0 is full ink
+        2 => cmyk_to_color(
+            1.0f32 - unit(values[0]),
+            1.0f32 - unit(values[1]),
+            1.0f32 - unit(values[2]),
+            1.0f32 - unit(values[3]),
+        ),
+        7 => Color::from(Lab::new(
+            values[0] as f32 / 100.0f32,
+            values[1] as i16 as f32 / 100.0f32,
+            values[2] as i16 as f32 / 100.0f32,
+        )),
+        // 0 - 10000 of black ink
+        8 => {
+            let gray = unit_to_u8(1.0f32 - values[0] as f32 / 10000.0f32);
+            Color::from_tuple((gray, gray, gray))
+        }
+        _ => return Err(format!("Unsupported color space: {}", space).into()),
+    };
+    Ok(color)
+}
+
+impl Palette {
+    /// a photoshop `.aco` swatch file, names come from the version 2 section
+    /// when there is one
+    /// rgb, hsb, cmyk (naive, without a profile), lab (treated as d65) and
+    /// grayscale records are read
+    pub fn from_aco(bytes: &[u8]) -> Result<Palette, Box<dyn Error>> {
+        let mut reader = Reader::new(bytes);
+        let mut palette = Palette::default();
+        while reader.remaining() > 0 {
+            let version = reader.u16()?;
+            if version != 1 && version != 2 {
+                return Err(format!("Unsupported aco version: {}", version).into());
+            }
+            let count = reader.u16()?;
+            let mut section = Palette::default();
+            for _ in 0..count {
+                let space = reader.u16()?;
+                let values = [reader.u16()?, reader.u16()?, reader.u16()?, reader.u16()?];
+                let name = match version {
+                    2 => {
+                        let units = reader.u32()? as usize;
+                        reader.utf16(units)?
+                    }
+                    _ => String::new(),
+                };
+                section.push(&name, aco_color(space, values)?);
+            }
+            palette = section;
+        }
+        Ok(palette)
+    }
+
+    /// a version 1 section followed by a version 2 section with names, as
+    /// photoshop writes them, every color in rgb
+    /// fails with more than 65535 entries
+    pub fn to_aco(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let count = u16::try_from(self.entries.len())
+            .map_err(|_| format!("Too many colors for an aco file: {}", self.entries.len()))?;
+        let mut bytes = Vec::new();
+        for version in [1u16, 2u16].iter() {
+            bytes.extend_from_slice(&version.to_be_bytes());
+            bytes.extend_from_slice(&count.to_be_bytes());
+            for entry in self.entries.iter() {
+                let (red, green, blue) = entry.color.to_tuple();
+                for value in [
+                    0u16,
+                    red as u16 * 257,
+                    green as u16 * 257,
+                    blue as u16 * 257,
+                    0u16,
+                ]
+                .iter()
+                {
+                    bytes.extend_from_slice(&value.to_be_bytes());
+                }
+                if *version == 2 {
+                    let units = utf16_units(&entry.name);
+                    bytes.extend_from_slice(&(units.len() as u32).to_be_bytes());
+                    push_utf16(&mut bytes, &units);
+                }
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+mod tests {
+    #[test]
+    fn aco_tests() {
+        use crate::palette::tests::*;
+        use crate::palette::*;
+        use crate::traits::*;
+        let palette = stripped(true, false);
+        let written = palette.to_aco().unwrap();
+        assert_eq!(&written[..4], &[0, 1, 0, 7]);
+        assert_eq!(&written[4..14], &[0, 0, 255, 255, 36, 36, 0, 0, 0, 0]);
+        assert_eq!(
+            Palette::from_aco(&written).unwrap().entries,
+            palette.entries
+        );
+
+        // a version 1 file alone has no names
+        let version1 = &written[..4 + 7 * 10];
+        let parsed = Palette::from_aco(version1).unwrap();
+        assert_eq!(parsed.entries, stripped(false, false).entries);
+
+        // hsb, cmyk, lab and grayscale records
+        let mut other = vec![0u8, 1, 0, 4];
+        for record in [
+            [1u16, 0, 65535, 65535, 0],
+            [2u16, 0, 65535, 65535, 65535],
+            [7u16, 10000, 0, 0, 0],
+            [8u16, 10000, 0, 0, 0],
+        ]
+        .iter()
+        {
+            for value in record.iter() {
+                other.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        let colors = Palette::from_aco(&other).unwrap().colors();
+        assert_eq!(colors[0], crate::Color::from_hex("#ff0000"));
+        assert_eq!(colors[1], crate::Color::from_hex("#00ffff"));
+        assert_eq!(colors[2], crate::Color::from_hex("#ffffff"));
+        assert_eq!(colors[3], crate::Color::from_hex("#000000"));
+
+        assert!(Palette::from_aco(&written[..20]).is_err());
+        assert!(Palette::from_aco(&[0, 3, 0, 0]).is_err());
+        assert!(Palette::from_aco(&[0, 1, 0, 1, 0, 9, 0, 0, 0, 0, 0, 0, 0, 0]).is_err());
+
+        let mut huge = Palette::new("Huge");
+        huge.entries = vec![PaletteEntry::new("", crate::Color::new()); 65536];
+        assert!(huge.to_aco().is_err());
+        huge.entries.pop();
+        assert!(huge.to_aco().is_ok());
+    }
+}
@@ -0,0 +1,193 @@
+use std::convert::TryFrom;
+use std::error::Error;
+
+use crate::palette::cmyk_to_color;
+use crate::palette::push_utf16;
+use crate::palette::unit_to_u8;
+use crate::palette::utf16_units;
+use crate::palette::Palette;
+use crate::palette::PaletteEntry;
+use crate::palette::Reader;
+use crate::space::Lab;
+use crate::traits::*;
+use crate::Color;
+
+const GROUP_START: u16 = 0xc001;
+const GROUP_END: u16 = 0xc002;
+const COLOR_ENTRY: u16 = 0x0001;
+/// global, spot and normal
+const NORMAL_COLOR: u16 = 2;
+
+fn push_block(bytes: &mut Vec<u8>, kind: u16, payload: &[u8]) {
+    bytes.extend_from_slice(&kind.to_be_bytes());
+    bytes.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(payload);
+}
+
+/// fails for names of more than 65534 utf-16 units
+fn name_payload(name: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let units = utf16_units(name);
+    let length = u16::try_from(units.len()).map_err(|_| {
+        format!(
+            "Name too long for an ase file: {} utf-16 units",
+            units.len()
+        )
+    })?;
+    let mut payload = length.to_be_bytes().to_vec();
+    push_utf16(&mut payload, &units);
+    Ok(payload)
+}
+
+impl Palette {
+    /// an adobe swatch exchange `.ase` file
+    /// rgb, gray, cmyk (naive, without a profile) and lab (l as 0.0 - 1.0,
+    /// treated as d65) entries are read, the spot / global flag is dropped
+    pub fn from_ase(bytes: &[u8]) -> Result<Palette, Box<dyn Error>> {
+        let mut reader = Reader::new(bytes);
+        if reader.take(4)? != b"ASEF" {
+            return Err("Missing ASEF signature".into());
+        }
+        let major = reader.u16()?;
+        reader.u16()?;
+        if major != 1 {
+            return Err(format!("Unsupported ase version: {}", major).into());
+        }
+        let count = reader.u32()?;
+        let mut palette = Palette::default();
+        let mut group: Option<String> = None;
+        for _ in 0..count {
+            let kind = reader.u16()?;
+            let length = reader.u32()? as usize;
+            let mut block = Reader::new(reader.take(length)?);
+            match kind {
+                GROUP_START => {
+                    let units = block.u16()? as usize;
+                    group = Some(block.utf16(units)?);
+                }
+                GROUP_END => group = None,
+                COLOR_ENTRY => {
+                    let units = block.u16()? as usize;
+                    let name = block.utf16(units)?;
+                    let model = block.take(4)?;
+                    let color = match model {
+                        b"RGB " => {
+                            let (red, green, blue) = (block.f32()?, block.f32()?, block.f32()?);
+                            Color::from_tuple((
+                                unit_to_u8(red),
+                                unit_to_u8(green),
+                                unit_to_u8(blue),
+                            ))
+                        }
+                        b"Gray" => {
+                            let gray = unit_to_u8(block.f32()?);
+                            Color::from_tuple((gray, gray, gray))
+                        }
+                        b"CMYK" => {
+                            cmyk_to_color(block.f32()?, block.f32()?, block.f32()?, block.f32()?)
+                        }
+                        b"LAB " => {
+                            let (l, a, b) = (block.f32()?, block.f32()?, block.f32()?);
+                            Color::from(Lab::new(l * 100.0f32, a, b))
+                        }
+                        _ => {
+                            return Err(format!(
+                                "Unsupported color model: {}",
+                                String::from_utf8_lossy(model)
+                            )
+                            .into())
+                        }
+                    };
+                    palette.entries.push(PaletteEntry {
+                        name,
+                        color,
+                        group: group.clone(),
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(palette)
+    }
+
+    /// every color as a normal rgb entry, grouped entries inside group blocks
+    /// fails when a name does not fit in an ase name field
+    pub fn to_ase(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut blocks = Vec::new();
+        let mut count = 0u32;
+        for (group, entries) in self.groups() {
+            if let Some(group) = group {
+                push_block(&mut blocks, GROUP_START, &name_payload(group)?);
+                count += 1;
+            }
+            for entry in entries.iter() {
+                let mut payload = name_payload(&entry.name)?;
+                payload.extend_from_slice(b"RGB ");
+                for channel in [entry.color.red, entry.color.green, entry.color.blue].iter() {
+                    payload.extend_from_slice(&(*channel as f32 / 255.0f32).to_be_bytes());
+                }
+                payload.extend_from_slice(&NORMAL_COLOR.to_be_bytes());
+                push_block(&mut blocks, COLOR_ENTRY, &payload);
+                count += 1;
+            }
+            if group.is_some() {
+                push_block(&mut blocks, GROUP_END, &[]);
+                count += 1;
+            }
+        }
+        let mut bytes = b"ASEF".to_vec();
+        bytes.extend_from_slice(&1u16.to_be_bytes());
+        bytes.extend_from_slice(&0u16.to_be_bytes());
+        bytes.extend_from_slice(&count.to_be_bytes());
+        bytes.extend_from_slice(&blocks);
+        Ok(bytes)
+    }
+}
+
+mod tests {
+    #[test]
+    fn ase_tests() {
+        use crate::palette::tests::*;
+        use crate::palette::*;
+        use crate::traits::*;
+        let palette = stripped(true, true);
+        let written = palette.to_ase().unwrap();
+        assert_eq!(&written[..12], b"ASEF\x00\x01\x00\x00\x00\x00\x00\x09");
+        // the first entry, "Scarlet" and its nul as utf-16
+        assert_eq!(&written[12..18], &[0x00, 0x01, 0x00, 0x00, 0x00, 0x24]);
+        assert_eq!(&written[18..22], &[0x00, 0x08, 0x00, 0x53]);
+        let parsed = Palette::from_ase(&written).unwrap();
+        assert_eq!(parsed.entries, palette.entries);
+
+        // gray, cmyk and lab entries
+        let mut other = b"ASEF\x00\x01\x00\x00\x00\x00\x00\x03".to_vec();
+        let entries: [(&[u8; 4], Vec<f32>); 3] = [
+            (b"Gray", vec![0.5]),
+            (b"CMYK", vec![0.0, 1.0, 1.0, 0.0]),
+            (b"LAB ", vec![1.0, 0.0, 0.0]),
+        ];
+        for (model, values) in entries.iter() {
+            let mut payload = vec![0u8, 1, 0, 0];
+            payload.extend_from_slice(*model);
+            for value in values.iter() {
+                payload.extend_from_slice(&value.to_be_bytes());
+            }
+            payload.extend_from_slice(&[0, 2]);
+            other.extend_from_slice(&[0, 1]);
+            other.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+            other.extend_from_slice(&payload);
+        }
+        let colors = Palette::from_ase(&other).unwrap().colors();
+        assert_eq!(colors[0], crate::Color::from_hex("#808080"));
+        assert_eq!(colors[1], crate::Color::from_hex("#ff0000"));
+        assert_eq!(colors[2], crate::Color::from_hex("#ffffff"));
+
+        assert!(Palette::from_ase(b"ASEX\x00\x01").is_err());
+
+        let mut long = Palette::new("Long");
+        long.push(&"a".repeat(65535), crate::Color::new());
+        assert!(long.to_ase().is_err());
+        long.entries[0].name.pop();
+        assert!(long.to_ase().is_ok());
+        assert!(Palette::from_ase(&written[..40]).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+use std::error::Error;
+
+use crate::palette::Palette;
+use crate::traits::*;
+use crate::Color;
+
+impl Palette {
+    /// a gimp `.gpl` palette, `Name:` and `Columns:` are optional and the
+    /// color name is whatever follows the three channels
+    pub fn from_gpl(s: &str) -> Result<Palette, Box<dyn Error>> {
+        let mut lines = s.lines();
+        match lines.next() {
+            Some(header) if header.trim() == "GIMP Palette" => {}
+            _ => return Err("Missing GIMP Palette header".into()),
+        }
+        let mut palette = Palette::default();
+        for (number, line) in lines.enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix("Name:") {
+                palette.name = name.trim().to_string();
+                continue;
+            }
+            if let Some(columns) = line.strip_prefix("Columns:") {
+                palette.columns = columns.trim().parse()?;
+                continue;
+            }
+            let mut rest = line;
+            let mut channels = [0u8; 3];
+            for channel in channels.iter_mut() {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                *channel = rest[..end]
+                    .parse()
+                    .map_err(|_| format!("Invalid color on line {}: {}", number + 2, line))?;
+                rest = rest[end..].trim_start();
+            }
+            palette.push(
+                rest,
+                Color::from_tuple((channels[0], channels[1], channels[2])),
+            );
+        }
+        Ok(palette)
+    }
+
+    pub fn to_gpl(&self) -> String {
+        let mut lines = vec!["GIMP Palette".to_string()];
+        if !self.name.is_empty() {
+            lines.push(format!("Name: {}", self.name));
+        }
+        if self.columns != 0 {
+            lines.push(format!("Columns: {}", self.columns));
+        }
+        lines.push("#".to_string());
+        for entry in self.entries.iter() {
+            let (red, green, blue) = entry.color.to_tuple();
+            let line = format!("{:3} {:3} {:3}\t{}", red, green, blue, entry.name);
+            lines.push(line.trim_end().to_string());
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+mod tests {
+    #[test]
+    fn gpl_tests() {
+        use crate::palette::tests::*;
+        use crate::palette::*;
+        let palette = stripped(true, false);
+        let written = palette.to_gpl();
+        assert!(written.starts_with(
+            "GIMP Palette\nName: Sample \"Palette\"\nColumns: 4\n#\n255  36   0\tScarlet\n  0   0   0\n"
+        ));
+        assert_eq!(Palette::from_gpl(&written).unwrap(), palette);
+
+        let gimp =
+            "GIMP Palette\r\nName: Tango\r\n# comment\r\n252 233  79\tButter 1\r\n 0 0 0\r\n";
+        let parsed = Palette::from_gpl(gimp).unwrap();
+        assert_eq!(parsed.name, "Tango");
+        assert_eq!(parsed.columns, 0);
+        assert_eq!(parsed.entries[0].name, "Butter 1");
+        assert_eq!(parsed.entries[1].color, crate::Color::new());
+        assert!(Palette::from_gpl("JASC-PAL\n").is_err());
+        assert!(Palette::from_gpl("GIMP Palette\n256 0 0 Too much\n").is_err());
+    }
+}
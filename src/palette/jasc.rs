@@ -0,0 +1,69 @@
+use std::error::Error;
+
+use crate::palette::Palette;
+use crate::traits::*;
+use crate::Color;
+
+impl Palette {
+    /// a jasc / paint shop pro `.pal` palette
+    pub fn from_jasc_pal(s: &str) -> Result<Palette, Box<dyn Error>> {
+        let mut lines = s.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines.next() != Some("JASC-PAL") {
+            return Err("Missing JASC-PAL header".into());
+        }
+        if lines.next() != Some("0100") {
+            return Err("Unsupported JASC-PAL version".into());
+        }
+        let count: usize = lines.next().ok_or("Missing color count")?.parse()?;
+        let mut palette = Palette::default();
+        for line in lines.by_ref().take(count) {
+            let channels: Vec<u8> = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| format!("Invalid color: {}", line))?;
+            match channels.as_slice() {
+                [red, green, blue] => palette.push("", Color::from_tuple((*red, *green, *blue))),
+                _ => return Err(format!("Invalid color: {}", line).into()),
+            }
+        }
+        if palette.entries.len() != count {
+            return Err(
+                format!("Expected {} colors, found {}", count, palette.entries.len()).into(),
+            );
+        }
+        Ok(palette)
+    }
+
+    /// jasc files use crlf line endings
+    pub fn to_jasc_pal(&self) -> String {
+        let mut lines = vec![
+            "JASC-PAL".to_string(),
+            "0100".to_string(),
+            self.entries.len().to_string(),
+        ];
+        for entry in self.entries.iter() {
+            let (red, green, blue) = entry.color.to_tuple();
+            lines.push(format!("{} {} {}", red, green, blue));
+        }
+        lines.join("\r\n") + "\r\n"
+    }
+}
+
+mod tests {
+    #[test]
+    fn jasc_tests() {
+        use crate::palette::tests::*;
+        use crate::palette::*;
+        let palette = stripped(false, false);
+        let written = palette.to_jasc_pal();
+        assert!(written.starts_with("JASC-PAL\r\n0100\r\n7\r\n255 36 0\r\n0 0 0\r\n"));
+        assert_eq!(
+            Palette::from_jasc_pal(&written).unwrap().entries,
+            palette.entries
+        );
+        assert!(Palette::from_jasc_pal("JASC-PAL\n0100\n2\n0 0 0\n").is_err());
+        assert!(Palette::from_jasc_pal("JASC-PAL\n0100\n1\n0 0\n").is_err());
+        assert!(Palette::from_jasc_pal("GIMP Palette\n").is_err());
+    }
+}
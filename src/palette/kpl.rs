@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Cursor;
+use std::io::Read;
+use std::io::Write;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use zip::write::SimpleFileOptions;
+use zip::CompressionMethod;
+use zip::ZipArchive;
+use zip::ZipWriter;
+
+use crate::palette::cmyk_to_color;
+use crate::palette::unit_to_u8;
+use crate::palette::Palette;
+use crate::palette::PaletteEntry;
+use crate::space::Lab;
+use crate::traits::*;
+use crate::Color;
+
+const MIMETYPE: &str = "krita/x-colorset";
+
+static ELEMENT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"<(/?)(ColorSet|ColorSetEntry|Group|RGB|Gray|Lab|CMYK)\b([^>]*?)(/?)>"#).unwrap()
+});
+
+static ATTRIBUTE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"([\w:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn attributes(text: &str) -> HashMap<String, String> {
+    ATTRIBUTE_REGEX
+        .captures_iter(text)
+        .map(|capture| {
+            let value = capture.get(2).or_else(|| capture.get(3)).unwrap();
+            (capture[1].to_string(), unescape(value.as_str()))
+        })
+        .collect()
+}
+
+/// a krita color element, components as krita writes them
+fn element_color(tag: &str, attributes: &HashMap<String, String>) -> Result<Color, Box<dyn Error>> {
+    let number = |name: &str| -> Result<f32, Box<dyn Error>> {
+        let value = attributes
+            .get(name)
+            .ok_or_else(|| format!("Missing {} on {}", name, tag))?;
+        Ok(value.trim().parse::<f32>()?)
+    };
+    let color = match tag {
+        "RGB" => Color::from_tuple((
+            unit_to_u8(number("r")?),
+            unit_to_u8(number("g")?),
+            unit_to_u8(number("b")?),
+        )),
+        "Gray" => {
+            let gray = unit_to_u8(number("g")?);
+            Color::from_tuple((gray, gray, gray))
+        }
+        "CMYK" => cmyk_to_color(number("c")?, number("m")?, number("y")?, number("k")?),
+        _ => Color::from(Lab::new(number("L")?, number("a")?, number("b")?)),
+    };
+    Ok(color)
+}
+
+impl Palette {
+    /// a krita `.kpl` palette, a zip with a `colorset.xml`
+    /// rgb, gray, cmyk (naive, without a profile) and lab (treated as d65)
+    /// entries are read in file order, profiles are ignored
+    pub fn from_kpl(bytes: &[u8]) -> Result<Palette, Box<dyn Error>> {
+        let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+        let mut xml = String::new();
+        archive.by_name("colorset.xml")?.read_to_string(&mut xml)?;
+        Palette::from_kpl_xml(&xml)
+    }
+
+    fn from_kpl_xml(xml: &str) -> Result<Palette, Box<dyn Error>> {
+        let mut palette = Palette::default();
+        let mut group: Option<String> = None;
+        let mut entry: Option<(String, Option<Color>)> = None;
+        for capture in ELEMENT_REGEX.captures_iter(xml) {
+            let closing = !capture[1].is_empty();
+            let self_closing = !capture[4].is_empty();
+            let tag = &capture[2];
+            let attributes = attributes(&capture[3]);
+            match (tag, closing) {
+                ("ColorSet", false) => {
+                    palette.name = attributes.get("name").cloned().unwrap_or_default();
+                    palette.columns = match attributes.get("columns") {
+                        Some(columns) => columns.trim().parse()?,
+                        None => 0,
+                    };
+                }
+                ("Group", false) if !self_closing => group = attributes.get("name").cloned(),
+                ("Group", true) => group = None,
+                ("ColorSetEntry", false) => {
+                    entry = Some((attributes.get("name").cloned().unwrap_or_default(), None));
+                }
+                ("ColorSetEntry", true) => {
+                    let (name, color) = entry.take().ok_or("Unexpected </ColorSetEntry>")?;
+                    palette.entries.push(PaletteEntry {
+                        color: color.ok_or_else(|| format!("Missing color for {}", name))?,
+                        name,
+                        group: group.clone(),
+                    });
+                }
+                (_, false) => {
+                    if let Some((_, color)) = entry.as_mut() {
+                        *color = Some(element_color(tag, &attributes)?);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(palette)
+    }
+
+    /// a krita 4 / 5 `.kpl` with every color as srgb, `columns` of 0 is written as 16
+    pub fn to_kpl(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        writer.start_file("mimetype", stored)?;
+        writer.write_all(MIMETYPE.as_bytes())?;
+        writer.start_file("colorset.xml", deflated)?;
+        writer.write_all(self.to_kpl_xml().as_bytes())?;
+        writer.start_file("profiles.xml", deflated)?;
+        writer.write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Profiles/>\n")?;
+        Ok(writer.finish()?.into_inner())
+    }
+
+    fn to_kpl_xml(&self) -> String {
+        let columns = match self.columns {
+            0 => 16,
+            columns => columns,
+        };
+        let rows = |count: usize| count.div_ceil(columns).max(1);
+        let groups = self.groups();
+        let ungrouped = groups
+            .iter()
+            .filter(|(group, _)| group.is_none())
+            .map(|(_, entries)| entries.len())
+            .sum::<usize>();
+        let mut xml = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<ColorSet version=\"2.0\" name=\"{}\" comment=\"\" columns=\"{}\" rows=\"{}\" readonly=\"false\">\n",
+            escape(&self.name),
+            columns,
+            rows(ungrouped)
+        );
+        let mut position = 0usize;
+        for (group, entries) in groups.iter() {
+            let indent = match group {
+                Some(group) => {
+                    xml.push_str(&format!(
+                        " <Group name=\"{}\" rows=\"{}\">\n",
+                        escape(group),
+                        rows(entries.len())
+                    ));
+                    "  "
+                }
+                None => " ",
+            };
+            for (index, entry) in entries.iter().enumerate() {
+                let index = match group {
+                    Some(_) => index,
+                    None => {
+                        position += 1;
+                        position - 1
+                    }
+                };
+                let component = |value: u8| value as f64 / 255.0f64;
+                xml.push_str(&format!(
+                    "{indent}<ColorSetEntry name=\"{name}\" id=\"{name}\" spot=\"false\" bitdepth=\"U8\">\n{indent} <RGB space=\"sRGB-elle-V2-srgbtrc.icc\" r=\"{r}\" g=\"{g}\" b=\"{b}\"/>\n{indent} <Position row=\"{row}\" column=\"{column}\"/>\n{indent}</ColorSetEntry>\n",
+                    indent = indent,
+                    name = escape(&entry.name),
+                    r = component(entry.color.red),
+                    g = component(entry.color.green),
+                    b = component(entry.color.blue),
+                    row = index / columns,
+                    column = index % columns,
+                ));
+            }
+            if group.is_some() {
+                xml.push_str(" </Group>\n");
+            }
+        }
+        xml.push_str("</ColorSet>\n");
+        xml
+    }
+}
+
+mod tests {
+    #[test]
+    fn kpl_tests() {
+        use crate::palette::tests::*;
+        use crate::palette::*;
+        use crate::traits::*;
+        let mut palette = stripped(true, true);
+        palette.entries[0].name = "<Red & \"Hot\">".to_string();
+        let written = palette.to_kpl().unwrap();
+        assert_eq!(&written[..4], b"PK\x03\x04");
+        // krita looks for an uncompressed mimetype first
+        assert_eq!(&written[30..38], b"mimetype");
+        assert_eq!(&written[38..54], b"krita/x-colorset");
+        let parsed = Palette::from_kpl(&written).unwrap();
+        assert_eq!(parsed, palette);
+
+        let xml = palette.to_kpl_xml();
+        assert!(xml.contains(" <Group name=\"Greens\" rows=\"1\">\n  <ColorSetEntry name=\"Sea\""));
+        assert!(xml.contains("name=\"&lt;Red &amp; &quot;Hot&quot;&gt;\""));
+
+        let krita = r#"<ColorSet name="Krita" columns="8" version="1.0">
+ <ColorSetEntry name="Ink" bitdepth="U16"><CMYK c="0" m="0" y="0" k="1"/></ColorSetEntry>
+ <ColorSetEntry name='Paper'><Gray g="1"/></ColorSetEntry>
+ <ColorSetEntry name="Lab"><Lab L="53.2408" a="80.0925" b="67.2032"/></ColorSetEntry>
+</ColorSet>"#;
+        let parsed = Palette::from_kpl_xml(krita).unwrap();
+        assert_eq!(parsed.columns, 8);
+        assert_eq!(
+            parsed.colors(),
+            vec![
+                crate::Color::from_hex("#000000"),
+                crate::Color::from_hex("#ffffff"),
+                crate::Color::from_hex("#ff0000"),
+            ]
+        );
+        assert!(Palette::from_kpl_xml(
+            "<ColorSet><ColorSetEntry name=\"x\"></ColorSetEntry></ColorSet>"
+        )
+        .is_err());
+        assert!(Palette::from_kpl(b"not a zip").is_err());
+    }
+}
@@ -0,0 +1,193 @@
+use std::error::Error;
+
+use crate::Color;
+
+pub mod aco;
+pub mod ase;
+pub mod gpl;
+pub mod jasc;
+pub mod kpl;
+pub mod paint_net;
+
+/// a named color, `group` is the ase or krita group it was listed under
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PaletteEntry {
+    pub name: String,
+    pub color: Color,
+    pub group: Option<String>,
+}
+
+impl PaletteEntry {
+    pub fn new(name: &str, color: Color) -> Self {
+        PaletteEntry {
+            name: name.to_string(),
+            color,
+            group: None,
+        }
+    }
+}
+
+/// an ordered list of colors as found in palette files
+/// formats without names, groups or a column count drop them when writing
+/// and leave them empty when reading
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Palette {
+    pub name: String,
+    /// preferred number of columns in a swatch grid, 0 when unspecified
+    pub columns: usize,
+    pub entries: Vec<PaletteEntry>,
+}
+
+impl Palette {
+    pub fn new(name: &str) -> Self {
+        Palette {
+            name: name.to_string(),
+            ..Palette::default()
+        }
+    }
+
+    pub fn push(&mut self, name: &str, color: Color) {
+        self.entries.push(PaletteEntry::new(name, color));
+    }
+
+    pub fn colors(&self) -> Vec<Color> {
+        self.entries.iter().map(|entry| entry.color).collect()
+    }
+
+    /// consecutive entries sharing a group, in order
+    fn groups(&self) -> Vec<(Option<&str>, &[PaletteEntry])> {
+        let mut groups: Vec<(Option<&str>, &[PaletteEntry])> = Vec::new();
+        let mut start = 0usize;
+        for index in 1..=self.entries.len() {
+            if index == self.entries.len() || self.entries[index].group != self.entries[start].group
+            {
+                groups.push((
+                    self.entries[start].group.as_deref(),
+                    &self.entries[start..index],
+                ));
+                start = index;
+            }
+        }
+        groups
+    }
+}
+
+/// big endian reads over a byte slice that fail instead of panicking
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, position: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        if self.remaining() < count {
+            return Err(format!("Unexpected end of data at byte {}", self.position).into());
+        }
+        let slice = &self.bytes[self.position..self.position + count];
+        self.position += count;
+        Ok(slice)
+    }
+
+    fn u16(&mut self) -> Result<u16, Box<dyn Error>> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn f32(&mut self) -> Result<f32, Box<dyn Error>> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+
+    /// `units` utf-16 code units, a trailing nul is dropped
+    fn utf16(&mut self, units: usize) -> Result<String, Box<dyn Error>> {
+        let mut text: Vec<u16> = (0..units).map(|_| self.u16()).collect::<Result<_, _>>()?;
+        if text.last() == Some(&0) {
+            text.pop();
+        }
+        Ok(String::from_utf16(&text)?)
+    }
+}
+
+/// `text` as big endian utf-16 code units followed by a nul
+fn utf16_units(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+fn push_utf16(bytes: &mut Vec<u8>, units: &[u16]) {
+    for unit in units.iter() {
+        bytes.extend_from_slice(&unit.to_be_bytes());
+    }
+}
+
+/// `value` out of 0.0 - 1.0 to 0 - 255, rounded
+fn unit_to_u8(value: f32) -> u8 {
+    crate::utils::unit_f32_to_u8(value.clamp(0.0f32, 1.0f32))
+}
+
+/// naive cmyk without a profile, every component 0.0 - 1.0
+fn cmyk_to_color(cyan: f32, magenta: f32, yellow: f32, key: f32) -> Color {
+    use crate::traits::FromTuple;
+    let channel = |ink: f32| unit_to_u8((1.0f32 - ink) * (1.0f32 - key));
+    Color::from_tuple((channel(cyan), channel(magenta), channel(yellow)))
+}
+
+pub(crate) mod tests {
+    #[allow(dead_code)]
+    pub(crate) fn sample() -> super::Palette {
+        use crate::traits::*;
+        let mut palette = super::Palette::new("Sample \"Palette\"");
+        palette.columns = 4;
+        palette.push("Scarlet", crate::Color::from_hex("#ff2400"));
+        palette.push("", crate::Color::from_hex("#000000"));
+        palette.push("Ünïcödé ✓", crate::Color::from_hex("#0a141e"));
+        palette.push("Mint", crate::Color::from_hex("#98ff98"));
+        let mut sea = super::PaletteEntry::new("Sea", crate::Color::from_hex("#2e8b57"));
+        sea.group = Some("Greens".to_string());
+        let mut moss = super::PaletteEntry::new("Moss", crate::Color::from_hex("#8a9a5b"));
+        moss.group = Some("Greens".to_string());
+        palette.entries.push(sea);
+        palette.entries.push(moss);
+        palette.push("White", crate::Color::from_hex("#ffffff"));
+        palette
+    }
+
+    /// `palette` with only what a format keeps
+    #[allow(dead_code)]
+    pub(crate) fn stripped(names: bool, groups: bool) -> super::Palette {
+        let mut palette = sample();
+        for entry in palette.entries.iter_mut() {
+            if !names {
+                entry.name.clear();
+            }
+            if !groups {
+                entry.group = None;
+            }
+        }
+        palette
+    }
+
+    #[test]
+    fn groups_tests() {
+        let palette = sample();
+        let groups = palette.groups();
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].0, None);
+        assert_eq!(groups[0].1.len(), 4);
+        assert_eq!(groups[1].0, Some("Greens"));
+        assert_eq!(groups[1].1.len(), 2);
+        assert_eq!(groups[2].1[0].name, "White");
+        assert!(super::Palette::new("").groups().is_empty());
+    }
+}
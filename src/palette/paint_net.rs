@@ -0,0 +1,62 @@
+use std::error::Error;
+
+use crate::palette::Palette;
+use crate::traits::*;
+use crate::Color;
+
+impl Palette {
+    /// a paint.net `.txt` palette, one `AARRGGBB` per line, `;` starts a comment
+    pub fn from_paint_net(s: &str) -> Result<Palette, Box<dyn Error>> {
+        let mut palette = Palette::default();
+        for (number, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            let value = u32::from_str_radix(line, 16)
+                .ok()
+                .filter(|_| line.len() == 8)
+                .ok_or_else(|| format!("Invalid color on line {}: {}", number + 1, line))?;
+            let [alpha, red, green, blue] = value.to_be_bytes();
+            palette.push("", Color::from_tuple_alpha((red, green, blue, alpha)));
+        }
+        Ok(palette)
+    }
+
+    /// paint.net shows at most 96 colors, the rest are still written
+    pub fn to_paint_net(&self) -> String {
+        let mut lines = vec![
+            "; paint.net Palette File".to_string(),
+            "; Lines that start with a semicolon are comments".to_string(),
+            "; Colors are written as 8-digit hexadecimal numbers: aarrggbb".to_string(),
+        ];
+        if !self.name.is_empty() {
+            lines.push(format!("; {}", self.name));
+        }
+        for entry in self.entries.iter() {
+            let (red, green, blue, alpha) = entry.color.to_tuple_alpha();
+            lines.push(format!("{:02X}{:02X}{:02X}{:02X}", alpha, red, green, blue));
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+mod tests {
+    #[test]
+    fn paint_net_tests() {
+        use crate::palette::tests::*;
+        use crate::palette::*;
+        use crate::traits::*;
+        let mut palette = stripped(false, false);
+        palette.entries[0].color = crate::Color::from_hex8("#ff240080");
+        let written = palette.to_paint_net();
+        assert!(written.contains("\n80FF2400\nFF000000\n"));
+        let parsed = Palette::from_paint_net(&written).unwrap();
+        assert_eq!(parsed.entries, palette.entries);
+        assert!(Palette::from_paint_net("FF00000\n").is_err());
+        assert!(Palette::from_paint_net("; only comments\n")
+            .unwrap()
+            .entries
+            .is_empty());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::Color;
+
+/// channel order and width of a color packed into an integer,
+/// named from the most significant bits down
+/// the 8 bit a channel layouts are lossless, the rest round each channel to
+/// the nearest level they can hold and drop alpha unless they have room for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PixelLayout {
+    Rgba8888,
+    Argb8888,
+    Abgr8888,
+    Bgra8888,
+    /// 16 bit, red in bits 11 - 15
+    Rgb565,
+    /// 16 bit, bit 15 unused
+    Rgb555,
+    /// 16 bit, red in bits 12 - 15
+    Rgba4444,
+    /// 8 bit, red in bits 5 - 7 and blue in bits 0 - 1
+    Rgb332,
+}
+
+/// which channel, 0 - 3 for red, green, blue and alpha, sits in each field
+/// from the least significant one up, along with the field's width
+fn fields(layout: PixelLayout) -> &'static [(usize, u32)] {
+    match layout {
+        PixelLayout::Rgba8888 => &[(3, 8), (2, 8), (1, 8), (0, 8)],
+        PixelLayout::Argb8888 => &[(2, 8), (1, 8), (0, 8), (3, 8)],
+        PixelLayout::Abgr8888 => &[(0, 8), (1, 8), (2, 8), (3, 8)],
+        PixelLayout::Bgra8888 => &[(3, 8), (0, 8), (1, 8), (2, 8)],
+        PixelLayout::Rgb565 => &[(2, 5), (1, 6), (0, 5)],
+        PixelLayout::Rgb555 => &[(2, 5), (1, 5), (0, 5)],
+        PixelLayout::Rgba4444 => &[(3, 4), (2, 4), (1, 4), (0, 4)],
+        PixelLayout::Rgb332 => &[(2, 2), (1, 3), (0, 3)],
+    }
+}
+
+/// `channel` rounded to `bits` bits
+fn reduce(channel: u8, bits: u32) -> u32 {
+    let max = (1u32 << bits) - 1;
+    (channel as u32 * max + 127) / 255
+}
+
+/// `value` of `bits` bits back to 8 by repeating its bits, so the largest
+/// value becomes 255 and every value survives a round trip through `reduce`
+fn expand(value: u32, bits: u32) -> u8 {
+    let mut result = 0u32;
+    let mut filled = 0u32;
+    while filled < 8 {
+        result = (result << bits) | value;
+        filled += bits;
+    }
+    (result >> (filled - 8)) as u8
+}
+
+impl Color {
+    pub fn to_u32(&self, layout: PixelLayout) -> u32 {
+        let channels = [self.red, self.green, self.blue, self.alpha];
+        let mut packed = 0u32;
+        let mut shift = 0u32;
+        for (channel, bits) in fields(layout).iter() {
+            packed |= reduce(channels[*channel], *bits) << shift;
+            shift += bits;
+        }
+        packed
+    }
+
+    /// bits above the layout's width are ignored, layouts without alpha are opaque
+    pub fn from_u32(packed: u32, layout: PixelLayout) -> Color {
+        let mut channels = [0u8, 0u8, 0u8, 255u8];
+        let mut shift = 0u32;
+        for (channel, bits) in fields(layout).iter() {
+            channels[*channel] = expand((packed >> shift) & ((1u32 << bits) - 1), *bits);
+            shift += bits;
+        }
+        Color::rgba(channels[0], channels[1], channels[2], channels[3])
+    }
+}
+
+mod tests {
+    #[test]
+    fn pixel_tests() {
+        use super::*;
+        let color = Color::rgba(0x12, 0x34, 0x56, 0x78);
+        assert_eq!(color.to_u32(PixelLayout::Rgba8888), 0x12345678);
+        assert_eq!(color.to_u32(PixelLayout::Argb8888), 0x78123456);
+        assert_eq!(color.to_u32(PixelLayout::Abgr8888), 0x78563412);
+        assert_eq!(color.to_u32(PixelLayout::Bgra8888), 0x56341278);
+        for layout in [
+            PixelLayout::Rgba8888,
+            PixelLayout::Argb8888,
+            PixelLayout::Abgr8888,
+            PixelLayout::Bgra8888,
+        ]
+        .iter()
+        {
+            assert_eq!(Color::from_u32(color.to_u32(*layout), *layout), color);
+        }
+
+        let orange = Color::rgb(255, 136, 0);
+        assert_eq!(orange.to_u32(PixelLayout::Rgb565), 0xfc40);
+        assert_eq!(orange.to_u32(PixelLayout::Rgb555), 0x7e20);
+        assert_eq!(orange.to_u32(PixelLayout::Rgba4444), 0xf80f);
+        assert_eq!(orange.to_u32(PixelLayout::Rgb332), 0xf0);
+        // rounded rather than truncated, 7 / 255 is nearer 1 / 31 than 0
+        assert_eq!(Color::rgb(7, 0, 0).to_u32(PixelLayout::Rgb565), 1 << 11);
+        assert_eq!(Color::rgb(3, 0, 0).to_u32(PixelLayout::Rgb565), 0);
+        assert_eq!(
+            Color::rgb(255, 255, 255).to_u32(PixelLayout::Rgb565),
+            0xffff
+        );
+        assert_eq!(
+            Color::from_u32(0xfc40, PixelLayout::Rgb565),
+            Color::rgb(255, 138, 0)
+        );
+        assert_eq!(
+            Color::from_u32(0xffff_ffff, PixelLayout::Rgb555),
+            Color::rgb(255, 255, 255)
+        );
+        assert_eq!(
+            Color::from_u32(0x8421, PixelLayout::Rgba4444),
+            Color::rgba(0x88, 0x44, 0x22, 0x11)
+        );
+        assert_eq!(
+            Color::from_u32(0b1010_1101, PixelLayout::Rgb332),
+            Color::rgb(0b10110110, 0b01101101, 0b01010101)
+        );
+
+        // every packed value expands to a color that packs back to it
+        for layout in [
+            PixelLayout::Rgb565,
+            PixelLayout::Rgb555,
+            PixelLayout::Rgba4444,
+            PixelLayout::Rgb332,
+        ]
+        .iter()
+        {
+            let width: u32 = fields(*layout).iter().map(|(_, bits)| bits).sum();
+            for packed in 0..1u32 << width {
+                assert_eq!(Color::from_u32(packed, *layout).to_u32(*layout), packed);
+            }
+        }
+    }
+}
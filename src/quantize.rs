@@ -0,0 +1,494 @@
+use std::collections::HashMap;
+
+use crate::space::Oklab;
+use crate::traits::*;
+use crate::Color;
+
+/// quantization algorithm
+/// `KMeans` runs in oklab with k-means++ seeding, the same seed always gives
+/// the same palette, the other two are deterministic on their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Method {
+    MedianCut,
+    Octree,
+    KMeans { seed: u64, iterations: usize },
+}
+
+/// a palette entry and how many pixels it stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Swatch {
+    pub color: Color,
+    pub population: usize,
+}
+
+/// reduces `pixels` to at most `colors` opaque swatches, most populous first
+/// fully transparent pixels are skipped, alpha is otherwise ignored
+pub fn quantize(pixels: &[Color], colors: usize, method: Method) -> Vec<Swatch> {
+    let histogram = histogram(pixels);
+    if colors == 0 || histogram.is_empty() {
+        return Vec::new();
+    }
+    let mut swatches = match histogram.len() <= colors {
+        true => histogram
+            .iter()
+            .map(|(color, count)| Swatch {
+                color: *color,
+                population: *count,
+            })
+            .collect(),
+        false => match method {
+            Method::MedianCut => median_cut(histogram, colors),
+            Method::Octree => octree(&histogram, colors),
+            Method::KMeans { seed, iterations } => k_means(&histogram, colors, seed, iterations),
+        },
+    };
+    swatches.sort_by(|first, second| {
+        second
+            .population
+            .cmp(&first.population)
+            .then(first.color.cmp(&second.color))
+    });
+    swatches
+}
+
+/// distinct opaque colors and their pixel counts, sorted by color
+fn histogram(pixels: &[Color]) -> Vec<(Color, usize)> {
+    let mut counts: HashMap<Color, usize> = HashMap::new();
+    for pixel in pixels.iter().filter(|pixel| pixel.alpha != 0) {
+        *counts
+            .entry(Color::from_tuple(pixel.to_tuple()))
+            .or_insert(0) += 1;
+    }
+    let mut histogram: Vec<(Color, usize)> = counts.into_iter().collect();
+    histogram.sort();
+    histogram
+}
+
+/// population weighted mean of `entries`
+fn average(entries: &[(Color, usize)]) -> Swatch {
+    let mut sums = [0u64; 3];
+    let mut population = 0usize;
+    for (color, count) in entries.iter() {
+        sums[0] += color.red as u64 * *count as u64;
+        sums[1] += color.green as u64 * *count as u64;
+        sums[2] += color.blue as u64 * *count as u64;
+        population += count;
+    }
+    let mean = |sum: u64| ((sum + population as u64 / 2) / population as u64) as u8;
+    Swatch {
+        color: Color::from_tuple((mean(sums[0]), mean(sums[1]), mean(sums[2]))),
+        population,
+    }
+}
+
+fn channel(color: &Color, index: usize) -> u8 {
+    match index {
+        0 => color.red,
+        1 => color.green,
+        _ => color.blue,
+    }
+}
+
+/// widest channel of a box and its range
+fn widest_channel(entries: &[(Color, usize)]) -> (usize, u8) {
+    (0..3)
+        .map(|index| {
+            let low = entries.iter().map(|(color, _)| channel(color, index)).min();
+            let high = entries.iter().map(|(color, _)| channel(color, index)).max();
+            (index, high.unwrap_or(0) - low.unwrap_or(0))
+        })
+        .max_by(|first, second| first.1.cmp(&second.1).then(second.0.cmp(&first.0)))
+        .unwrap()
+}
+
+/// heckbert's median cut, the box with the widest channel is split at its
+/// population median until there are enough boxes
+fn median_cut(histogram: Vec<(Color, usize)>, colors: usize) -> Vec<Swatch> {
+    let mut boxes = vec![histogram];
+    while boxes.len() < colors {
+        let candidate = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, entries)| entries.len() > 1)
+            .map(|(position, entries)| (position, widest_channel(entries).1))
+            .max_by(|first, second| first.1.cmp(&second.1).then(second.0.cmp(&first.0)));
+        let position = match candidate {
+            Some((position, _)) => position,
+            None => break,
+        };
+        let mut entries = boxes.swap_remove(position);
+        let (index, _) = widest_channel(&entries);
+        entries.sort_by(|first, second| {
+            channel(&first.0, index)
+                .cmp(&channel(&second.0, index))
+                .then(first.0.cmp(&second.0))
+        });
+        let total: usize = entries.iter().map(|(_, count)| count).sum();
+        let mut running = 0usize;
+        let mut split = entries.len() - 1;
+        for (offset, (_, count)) in entries.iter().enumerate() {
+            running += count;
+            if running * 2 >= total {
+                split = offset + 1;
+                break;
+            }
+        }
+        let split = split.clamp(1, entries.len() - 1);
+        let upper = entries.split_off(split);
+        boxes.push(entries);
+        boxes.push(upper);
+    }
+    boxes.iter().map(|entries| average(entries)).collect()
+}
+
+const NO_CHILD: usize = usize::MAX;
+
+struct OctreeNode {
+    children: [usize; 8],
+    sums: [u64; 3],
+    population: usize,
+    leaf: bool,
+}
+
+impl OctreeNode {
+    fn new(leaf: bool) -> Self {
+        OctreeNode {
+            children: [NO_CHILD; 8],
+            sums: [0; 3],
+            population: 0,
+            leaf,
+        }
+    }
+}
+
+/// gervautz and purgathofer's octree, the least populous node of the deepest
+/// level is folded into its parent until few enough leaves remain
+fn octree(histogram: &[(Color, usize)], colors: usize) -> Vec<Swatch> {
+    let mut nodes = vec![OctreeNode::new(false)];
+    let mut levels: Vec<Vec<usize>> = vec![Vec::new(); 8];
+    let mut leaves = 0usize;
+
+    for (color, count) in histogram.iter() {
+        let mut current = 0usize;
+        nodes[current].population += count;
+        for level in 0..8 {
+            let shift = 7 - level;
+            let branch = ((((color.red >> shift) & 1) << 2)
+                | (((color.green >> shift) & 1) << 1)
+                | ((color.blue >> shift) & 1)) as usize;
+            if nodes[current].children[branch] == NO_CHILD {
+                nodes.push(OctreeNode::new(level == 7));
+                let child = nodes.len() - 1;
+                nodes[current].children[branch] = child;
+                match level == 7 {
+                    true => leaves += 1,
+                    false => levels[level + 1].push(child),
+                }
+            }
+            current = nodes[current].children[branch];
+            nodes[current].population += count;
+        }
+        let leaf = &mut nodes[current];
+        leaf.sums[0] += color.red as u64 * *count as u64;
+        leaf.sums[1] += color.green as u64 * *count as u64;
+        leaf.sums[2] += color.blue as u64 * *count as u64;
+    }
+
+    // every node already counts its whole subtree, so the least populous node
+    // of a level can be popped off the end
+    levels[0].push(0);
+    for reducible in levels.iter_mut() {
+        reducible.sort_by_key(|node| (std::cmp::Reverse(nodes[*node].population), *node));
+    }
+    let mut level = 7;
+    while leaves > colors {
+        while levels[level].is_empty() {
+            level -= 1;
+        }
+        let node = levels[level].pop().unwrap();
+        let children = nodes[node].children;
+        let mut merged = 0usize;
+        for child in children.iter().filter(|child| **child != NO_CHILD) {
+            let sums = nodes[*child].sums;
+            for (total, sum) in nodes[node].sums.iter_mut().zip(sums.iter()) {
+                *total += sum;
+            }
+            merged += 1;
+        }
+        nodes[node].children = [NO_CHILD; 8];
+        nodes[node].leaf = true;
+        leaves = leaves + 1 - merged;
+    }
+
+    let mut swatches = Vec::new();
+    let mut stack = vec![0usize];
+    while let Some(node) = stack.pop() {
+        let node = &nodes[node];
+        if node.leaf {
+            let population = node.population as u64;
+            let mean = |sum: u64| ((sum + population / 2) / population) as u8;
+            swatches.push(Swatch {
+                color: Color::from_tuple((
+                    mean(node.sums[0]),
+                    mean(node.sums[1]),
+                    mean(node.sums[2]),
+                )),
+                population: node.population,
+            });
+        } else {
+            stack.extend(node.children.iter().filter(|child| **child != NO_CHILD));
+        }
+    }
+    swatches
+}
+
+/// splitmix64, small and good enough for seeding
+pub(crate) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// uniform in 0.0 - 1.0
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn squared_distance(first: &[f64; 3], second: &[f64; 3]) -> f64 {
+    (first[0] - second[0]).powi(2) + (first[1] - second[1]).powi(2) + (first[2] - second[2]).powi(2)
+}
+
+/// picks an index with probability proportional to its weight
+fn weighted_pick(weights: &[f64], random: &mut SplitMix64) -> usize {
+    let total: f64 = weights.iter().sum();
+    let mut target = random.next_f64() * total;
+    for (index, weight) in weights.iter().enumerate() {
+        if target < *weight {
+            return index;
+        }
+        target -= weight;
+    }
+    weights
+        .iter()
+        .rposition(|weight| *weight > 0.0)
+        .unwrap_or(0)
+}
+
+/// lloyd's k-means over the histogram in oklab, seeded with k-means++
+fn k_means(
+    histogram: &[(Color, usize)],
+    colors: usize,
+    seed: u64,
+    iterations: usize,
+) -> Vec<Swatch> {
+    let points: Vec<[f64; 3]> = histogram
+        .iter()
+        .map(|(color, _)| {
+            let oklab = Oklab::from(*color);
+            [oklab.l as f64, oklab.a as f64, oklab.b as f64]
+        })
+        .collect();
+    let weights: Vec<f64> = histogram.iter().map(|(_, count)| *count as f64).collect();
+    let mut random = SplitMix64::new(seed);
+
+    let mut centers = vec![points[weighted_pick(&weights, &mut random)]];
+    let mut nearest: Vec<f64> = points
+        .iter()
+        .map(|point| squared_distance(point, &centers[0]))
+        .collect();
+    while centers.len() < colors {
+        let scores: Vec<f64> = nearest
+            .iter()
+            .zip(weights.iter())
+            .map(|(distance, weight)| distance * weight)
+            .collect();
+        if scores.iter().all(|score| *score <= 0.0) {
+            break;
+        }
+        let center = points[weighted_pick(&scores, &mut random)];
+        for (point, distance) in points.iter().zip(nearest.iter_mut()) {
+            *distance = distance.min(squared_distance(point, &center));
+        }
+        centers.push(center);
+    }
+
+    let mut assignment = vec![usize::MAX; points.len()];
+    for _ in 0..iterations.max(1) {
+        let mut changed = false;
+        for (point, assigned) in points.iter().zip(assignment.iter_mut()) {
+            let closest = (0..centers.len())
+                .min_by(|first, second| {
+                    squared_distance(point, &centers[*first])
+                        .partial_cmp(&squared_distance(point, &centers[*second]))
+                        .unwrap()
+                })
+                .unwrap();
+            if *assigned != closest {
+                *assigned = closest;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+        let mut sums = vec![([0.0f64; 3], 0.0f64); centers.len()];
+        for ((point, weight), assigned) in points.iter().zip(weights.iter()).zip(assignment.iter())
+        {
+            let (sum, total) = &mut sums[*assigned];
+            for index in 0..3 {
+                sum[index] += point[index] * weight;
+            }
+            *total += weight;
+        }
+        for (center, (sum, total)) in centers.iter_mut().zip(sums.iter()) {
+            if *total > 0.0 {
+                *center = [sum[0] / total, sum[1] / total, sum[2] / total];
+            }
+        }
+    }
+
+    let mut populations = vec![0usize; centers.len()];
+    for ((_, count), assigned) in histogram.iter().zip(assignment.iter()) {
+        populations[*assigned] += count;
+    }
+    centers
+        .iter()
+        .zip(populations.iter())
+        .filter(|(_, population)| **population > 0)
+        .map(|(center, population)| Swatch {
+            color: Color::from(Oklab::new(
+                center[0] as f32,
+                center[1] as f32,
+                center[2] as f32,
+            )),
+            population: *population,
+        })
+        .collect()
+}
+
+mod tests {
+    #[allow(dead_code)]
+    fn gradient() -> Vec<crate::Color> {
+        use crate::traits::*;
+        let mut pixels = Vec::new();
+        for red in 0..32u8 {
+            for green in 0..32u8 {
+                for blue in 0..4u8 {
+                    pixels.push(crate::Color::from_tuple((red * 8, green * 8, blue * 64)));
+                }
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn quantize_tests() {
+        use super::*;
+        let pixels = gradient();
+        let methods = [
+            Method::MedianCut,
+            Method::Octree,
+            Method::KMeans {
+                seed: 7,
+                iterations: 16,
+            },
+        ];
+        for method in methods.iter() {
+            let swatches = quantize(&pixels, 8, *method);
+            assert!(!swatches.is_empty() && swatches.len() <= 8, "{:?}", method);
+            let total: usize = swatches.iter().map(|swatch| swatch.population).sum();
+            assert_eq!(total, pixels.len(), "{:?}", method);
+            for pair in swatches.windows(2) {
+                assert!(pair[0].population >= pair[1].population);
+            }
+            assert_eq!(swatches, quantize(&pixels, 8, *method));
+        }
+    }
+    #[test]
+    fn exact_colors_tests() {
+        use super::*;
+        let red = Color::from_hex("#ff0000");
+        let blue = Color::from_hex("#0000ff");
+        let transparent = Color::from_hex8("#00ff0000");
+        let mut pixels = vec![red; 30];
+        pixels.extend(vec![blue; 10]);
+        pixels.extend(vec![transparent; 50]);
+        let methods = [
+            Method::MedianCut,
+            Method::Octree,
+            Method::KMeans {
+                seed: 1,
+                iterations: 8,
+            },
+        ];
+        for method in methods.iter() {
+            let swatches = quantize(&pixels, 4, *method);
+            assert_eq!(
+                swatches,
+                vec![
+                    Swatch {
+                        color: red,
+                        population: 30
+                    },
+                    Swatch {
+                        color: blue,
+                        population: 10
+                    },
+                ]
+            );
+            assert_eq!(quantize(&pixels, 1, *method).len(), 1);
+        }
+        assert!(quantize(&[], 4, Method::Octree).is_empty());
+    }
+    #[test]
+    fn seed_tests() {
+        use super::*;
+        let pixels = gradient();
+        let first = quantize(
+            &pixels,
+            6,
+            Method::KMeans {
+                seed: 1,
+                iterations: 10,
+            },
+        );
+        let again = quantize(
+            &pixels,
+            6,
+            Method::KMeans {
+                seed: 1,
+                iterations: 10,
+            },
+        );
+        assert_eq!(first, again);
+        for seed in [2u64, 42, u64::MAX].iter() {
+            let other = quantize(
+                &pixels,
+                6,
+                Method::KMeans {
+                    seed: *seed,
+                    iterations: 10,
+                },
+            );
+            assert_eq!(other.len(), 6, "seed {}", seed);
+            let total: usize = other.iter().map(|swatch| swatch.population).sum();
+            assert_eq!(total, pixels.len(), "seed {}", seed);
+            for (index, swatch) in other.iter().enumerate() {
+                assert!(swatch.population > 0, "seed {}", seed);
+                assert!(other[index + 1..]
+                    .iter()
+                    .all(|later| later.color != swatch.color));
+            }
+        }
+    }
+}
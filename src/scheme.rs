@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use serde::de;
+use serde::ser::SerializeMap;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+use crate::traits::*;
+use crate::Color;
+
+/// a 16 color terminal scheme, `colors` holds color0 - color15
+/// serializes to the layout of pywal's `colors.json`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TerminalScheme {
+    pub background: Color,
+    pub foreground: Color,
+    pub cursor: Color,
+    pub colors: [Color; 16],
+}
+
+pub(crate) fn parse_color(name: &str, value: &str) -> Result<Color, Box<dyn Error>> {
+    value
+        .trim()
+        .parse::<Color>()
+        .map_err(|error| format!("Invalid color for {}: {}", name, error).into())
+}
+
+/// background, foreground, cursor and color0 - color15 out of named entries,
+/// a missing cursor falls back to the foreground
+pub(crate) fn from_named(
+    values: &HashMap<String, Color>,
+) -> Result<TerminalScheme, Box<dyn Error>> {
+    let get = |name: &str| -> Result<Color, Box<dyn Error>> {
+        values
+            .get(name)
+            .copied()
+            .ok_or_else(|| format!("Missing {}", name).into())
+    };
+    let foreground = get("foreground")?;
+    let mut colors = [Color::new(); 16];
+    for (index, color) in colors.iter_mut().enumerate() {
+        *color = get(&format!("color{}", index))?;
+    }
+    Ok(TerminalScheme {
+        background: get("background")?,
+        foreground,
+        cursor: get("cursor").unwrap_or(foreground),
+        colors,
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+struct PywalSpecial {
+    background: String,
+    foreground: String,
+    #[serde(default)]
+    cursor: Option<String>,
+}
+
+/// color0 - color15 in order, a map would sort color10 before color2
+struct PywalColors<'a>(&'a [Color; 16]);
+
+impl Serialize for PywalColors<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(16))?;
+        for (index, color) in self.0.iter().enumerate() {
+            map.serialize_entry(&format!("color{}", index), &color.hex())?;
+        }
+        map.end()
+    }
+}
+
+#[derive(Serialize)]
+struct PywalOut<'a> {
+    special: PywalSpecial,
+    colors: PywalColors<'a>,
+}
+
+/// other keys such as `wallpaper` and `alpha` are ignored
+#[derive(Deserialize)]
+struct PywalIn {
+    special: PywalSpecial,
+    colors: HashMap<String, String>,
+}
+
+impl Serialize for TerminalScheme {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PywalOut {
+            special: PywalSpecial {
+                background: self.background.hex(),
+                foreground: self.foreground.hex(),
+                cursor: Some(self.cursor.hex()),
+            },
+            colors: PywalColors(&self.colors),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TerminalScheme {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pywal = PywalIn::deserialize(deserializer)?;
+        let mut values = HashMap::new();
+        let special = vec![
+            ("background", Some(pywal.special.background)),
+            ("foreground", Some(pywal.special.foreground)),
+            ("cursor", pywal.special.cursor),
+        ];
+        let entries = special
+            .into_iter()
+            .filter_map(|(name, value)| value.map(|value| (name.to_string(), value)))
+            .chain(pywal.colors);
+        for (name, value) in entries {
+            let color = parse_color(&name, &value).map_err(de::Error::custom)?;
+            values.insert(name, color);
+        }
+        from_named(&values).map_err(de::Error::custom)
+    }
+}
+
+impl TerminalScheme {
+    /// `*.name: #rrggbb` lines for the special colors and color0 - color15
+    pub fn to_xresources(&self) -> String {
+        let mut lines = vec![
+            format!("*.foreground: {}", self.foreground.hex()),
+            format!("*.background: {}", self.background.hex()),
+            format!("*.cursorColor: {}", self.cursor.hex()),
+        ];
+        for (index, color) in self.colors.iter().enumerate() {
+            lines.push(format!("*.color{}: {}", index, color.hex()));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// reads `*name` and `*.name` resources, skipping `!` comments, resources
+    /// for a single program (`URxvt*background`) and anything that is not a
+    /// color, `#define` names are substituted and an `[alpha]` prefix is dropped
+    pub fn from_xresources(s: &str) -> Result<TerminalScheme, Box<dyn Error>> {
+        let mut defines: HashMap<&str, &str> = HashMap::new();
+        let mut values = HashMap::new();
+        for line in s.lines().map(str::trim) {
+            if let Some(define) = line.strip_prefix("#define") {
+                let mut parts = define.split_whitespace();
+                if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+                    defines.insert(name, value);
+                }
+                continue;
+            }
+            if line.is_empty() || line.starts_with('!') || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once(':') {
+                Some(pair) => pair,
+                None => continue,
+            };
+            let key = key.trim();
+            let name = match key.strip_prefix("*.").or_else(|| key.strip_prefix('*')) {
+                Some(name) => name,
+                None => continue,
+            };
+            let name = match name {
+                "cursorColor" => "cursor",
+                "background" | "foreground" => name,
+                _ if (0..16).any(|index| name == format!("color{}", index)) => name,
+                _ => continue,
+            };
+            let mut value = value.trim();
+            if let Some(end) = value.strip_prefix('[').and_then(|rest| rest.find(']')) {
+                value = &value[end + 2..];
+            }
+            let value = defines.get(value).copied().unwrap_or(value);
+            values.insert(name.to_string(), parse_color(key, value)?);
+        }
+        from_named(&values)
+    }
+
+    /// pywal's plain `colors` file, one `#rrggbb` per line for color0 - color15
+    pub fn to_colors_file(&self) -> String {
+        self.colors.iter().map(|color| color.hex() + "\n").collect()
+    }
+
+    /// reads a `colors` file, pywal uses color0 as the background and color15
+    /// as the foreground and cursor
+    pub fn from_colors_file(s: &str) -> Result<TerminalScheme, Box<dyn Error>> {
+        let lines: Vec<&str> = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+        if lines.len() != 16 {
+            return Err(format!("Expected 16 colors, found {}", lines.len()).into());
+        }
+        let mut colors = [Color::new(); 16];
+        for (index, (color, line)) in colors.iter_mut().zip(lines.iter()).enumerate() {
+            *color = parse_color(&format!("color{}", index), line)?;
+        }
+        Ok(TerminalScheme {
+            background: colors[0],
+            foreground: colors[15],
+            cursor: colors[15],
+            colors,
+        })
+    }
+}
+
+mod tests {
+    #[allow(dead_code)]
+    fn sample() -> super::TerminalScheme {
+        use crate::traits::*;
+        let mut colors = [crate::Color::new(); 16];
+        for (index, color) in colors.iter_mut().enumerate() {
+            let index = index as u8;
+            *color = crate::Color::from_tuple((index * 16, 255 - index * 16, index * 7));
+        }
+        super::TerminalScheme {
+            background: crate::Color::from_hex("#101820"),
+            foreground: crate::Color::from_hex("#e8e4d8"),
+            cursor: crate::Color::from_hex("#ffcc11"),
+            colors,
+        }
+    }
+
+    #[test]
+    fn pywal_json_tests() {
+        use super::*;
+        let scheme = sample();
+        let json = serde_json::to_string(&scheme).unwrap();
+        assert!(json.starts_with(r##"{"special":{"background":"#101820","foreground":"#e8e4d8","cursor":"#ffcc11"},"colors":{"color0":"#00ff00","color1":"#10ef07","color2""##));
+        assert_eq!(
+            serde_json::from_str::<TerminalScheme>(&json).unwrap(),
+            scheme
+        );
+
+        let mut pywal = String::from(
+            r##"{
+    "wallpaper": "/home/user/wallpaper.png",
+    "alpha": "100",
+    "special": {
+        "background": "#0c0d10",
+        "foreground": "#c2c2c3",
+        "cursor": "#c2c2c3"
+    },
+    "colors": {"##,
+        );
+        for index in 0..16 {
+            pywal.push_str(&format!(
+                "\n        \"color{}\": \"#{:02x}2233\",",
+                index, index
+            ));
+        }
+        pywal.pop();
+        pywal.push_str("\n    }\n}");
+        let parsed: TerminalScheme = serde_json::from_str(&pywal).unwrap();
+        assert_eq!(parsed.background, Color::from_hex("#0c0d10"));
+        assert_eq!(parsed.cursor, Color::from_hex("#c2c2c3"));
+        assert_eq!(parsed.colors[15], Color::from_hex("#0f2233"));
+
+        let missing = pywal.replace("\"color9\"", "\"color99\"");
+        let error = serde_json::from_str::<TerminalScheme>(&missing).unwrap_err();
+        assert!(error.to_string().contains("Missing color9"));
+        let invalid = pywal.replace("#0c0d10", "#0c0d1");
+        assert!(serde_json::from_str::<TerminalScheme>(&invalid).is_err());
+    }
+    #[test]
+    fn xresources_tests() {
+        use super::*;
+        let scheme = sample();
+        let written = scheme.to_xresources();
+        assert!(written.starts_with("*.foreground: #e8e4d8\n*.background: #101820\n"));
+        assert_eq!(TerminalScheme::from_xresources(&written).unwrap(), scheme);
+
+        let mut xresources = String::from(
+            "! special\n#define fg #c2c2c3\n*foreground: fg\n*.background:   #0c0d10\nURxvt*background: [90]#ff0000\n*.font: monospace\n\n",
+        );
+        for index in 0..16 {
+            xresources.push_str(&format!("*color{}: #{:02x}2233\n", index, index));
+        }
+        let parsed = TerminalScheme::from_xresources(&xresources).unwrap();
+        assert_eq!(parsed.background, Color::from_hex("#0c0d10"));
+        assert_eq!(parsed.foreground, Color::from_hex("#c2c2c3"));
+        assert_eq!(parsed.cursor, parsed.foreground);
+        assert_eq!(parsed.colors[10], Color::from_hex("#0a2233"));
+
+        let alpha = xresources.replace("*.background:   #0c0d10", "*.background: [90]#0c0d10");
+        let parsed = TerminalScheme::from_xresources(&alpha).unwrap();
+        assert_eq!(parsed.background, Color::from_hex("#0c0d10"));
+        assert!(TerminalScheme::from_xresources("*.background: #000000").is_err());
+        assert!(TerminalScheme::from_xresources(&xresources.replace("#0c0d10", "nope")).is_err());
+    }
+    #[test]
+    fn colors_file_tests() {
+        use super::*;
+        let scheme = sample();
+        let written = scheme.to_colors_file();
+        assert_eq!(written.lines().count(), 16);
+        assert!(written.starts_with("#00ff00\n#10ef07\n"));
+        let parsed = TerminalScheme::from_colors_file(&written).unwrap();
+        assert_eq!(parsed.colors, scheme.colors);
+        assert_eq!(parsed.background, scheme.colors[0]);
+        assert_eq!(parsed.foreground, scheme.colors[15]);
+        assert!(TerminalScheme::from_colors_file("#000000\n#ffffff\n").is_err());
+    }
+}
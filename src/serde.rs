@@ -0,0 +1,357 @@
+use std::fmt::Formatter;
+use std::marker::PhantomData;
+
+use ::serde::de;
+use ::serde::de::value::MapAccessDeserializer;
+use ::serde::Deserialize;
+use ::serde::Deserializer;
+use ::serde::Serialize;
+use ::serde::Serializer;
+
+use crate::format::ColorFormat;
+use crate::traits::*;
+use crate::utils;
+use crate::Color;
+
+/// the text form a `Text` field is written in
+pub trait Notation {
+    /// `None` picks hex for opaque colors and hex8 for the rest
+    const FORMAT: Option<ColorFormat>;
+}
+
+/// `#rrggbb`, alpha is dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Hex;
+
+/// `#rrggbbaa`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Hex8;
+
+/// `rgba(r,g,b,a)`, alpha with at least three digits and more when it takes
+/// them to read back the same value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rgba;
+
+/// `#rrggbb` when opaque and `#rrggbbaa` otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Any;
+
+impl Notation for Hex {
+    const FORMAT: Option<ColorFormat> = Some(ColorFormat::Hex);
+}
+
+impl Notation for Hex8 {
+    const FORMAT: Option<ColorFormat> = Some(ColorFormat::Hex8);
+}
+
+impl Notation for Rgba {
+    const FORMAT: Option<ColorFormat> = Some(ColorFormat::Rgba);
+}
+
+impl Notation for Any {
+    const FORMAT: Option<ColorFormat> = None;
+}
+
+/// `alpha` out of 0 - 255 as the shortest 0.0 - 1.0 decimal, with at least
+/// three digits, that `rgba()` parsing truncates back to the same byte
+fn alpha_fraction(alpha: u8) -> String {
+    let exact = alpha as f64 / 255.0f64;
+    (3..=6)
+        .map(|digits| {
+            let scale = 10.0f64.powi(digits as i32);
+            format!("{:.*}", digits, (exact * scale).ceil() / scale)
+        })
+        .find(|text| utils::clamped_f32_to_u8(text.parse().unwrap()) == alpha)
+        .unwrap()
+}
+
+fn serialize_as<S: Serializer>(
+    color: &Color,
+    format: Option<ColorFormat>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let format = format.unwrap_or(match color.alpha {
+        255 => ColorFormat::Hex,
+        _ => ColorFormat::Hex8,
+    });
+    match format {
+        // `ColorFormat::Rgba` rounds alpha to three digits and drops the decimal
+        // point for 0 and 1, neither of which survives a round trip
+        ColorFormat::Rgba => serializer.collect_str(&format_args!(
+            "rgba({},{},{},{})",
+            color.red,
+            color.green,
+            color.blue,
+            alpha_fraction(color.alpha)
+        )),
+        _ => serializer.collect_str(&color.display_format(format)),
+    }
+}
+
+/// reads any string `Color::from_str` takes, and the derived
+/// `{red, green, blue, alpha}` struct when `structs` is set
+struct ColorVisitor {
+    structs: bool,
+}
+
+impl<'de> de::Visitor<'de> for ColorVisitor {
+    type Value = Color;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("a color string such as #rrggbb, #rrggbbaa, rgb(), rgba(), ")?;
+        match self.structs {
+            true => formatter.write_str("rr/gg/bb/aa, 0xrrggbb or a color struct"),
+            false => formatter.write_str("rr/gg/bb/aa or 0xrrggbb"),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Color, E> {
+        v.trim()
+            .parse()
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Color, A::Error> {
+        if !self.structs {
+            return Err(de::Error::invalid_type(de::Unexpected::Map, &self));
+        }
+        Color::deserialize(MapAccessDeserializer::new(map))
+    }
+}
+
+fn deserialize_from<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    structs: bool,
+) -> Result<Color, D::Error> {
+    match structs {
+        true => deserializer.deserialize_any(ColorVisitor { structs }),
+        false => deserializer.deserialize_str(ColorVisitor { structs }),
+    }
+}
+
+/// `#[serde(with = "colordata::serde::hex")]`, writes `#rrggbb` and reads any
+/// color string
+pub mod hex {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_as(color, Hex::FORMAT, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        deserialize_from(deserializer, false)
+    }
+}
+
+/// `#[serde(with = "colordata::serde::hex8")]`, writes `#rrggbbaa` and reads
+/// any color string
+pub mod hex8 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_as(color, Hex8::FORMAT, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        deserialize_from(deserializer, false)
+    }
+}
+
+/// `#[serde(with = "colordata::serde::rgba")]`, writes `rgba(r,g,b,a)` and
+/// reads any color string
+pub mod rgba {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_as(color, Rgba::FORMAT, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        deserialize_from(deserializer, false)
+    }
+}
+
+/// `#[serde(with = "colordata::serde::any")]`, writes `#rrggbb` or
+/// `#rrggbbaa` and reads any color string as well as the derived struct form,
+/// for files written before the switch to strings
+/// the struct form needs a self describing format such as json
+pub mod any {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_as(color, Any::FORMAT, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        deserialize_from(deserializer, true)
+    }
+}
+
+/// a `Color` that serializes as text in notation `N` and deserializes from
+/// any color string, for places `with` can not reach such as `Vec<Text<Hex>>`
+/// or `HashMap<String, Text<Rgba>>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Text<N: Notation> {
+    pub color: Color,
+    notation: PhantomData<N>,
+}
+
+impl<N: Notation> Text<N> {
+    pub fn new(color: Color) -> Self {
+        Text {
+            color,
+            notation: PhantomData,
+        }
+    }
+}
+
+impl<N: Notation> From<Color> for Text<N> {
+    fn from(color: Color) -> Self {
+        Text::new(color)
+    }
+}
+
+impl<N: Notation> From<Text<N>> for Color {
+    fn from(text: Text<N>) -> Self {
+        text.color
+    }
+}
+
+impl<N: Notation> Serialize for Text<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_as(&self.color, N::FORMAT, serializer)
+    }
+}
+
+impl<'de, N: Notation> Deserialize<'de> for Text<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_from(deserializer, false).map(Text::new)
+    }
+}
+
+mod tests {
+    #[test]
+    fn with_tests() {
+        use super::*;
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Theme {
+            #[serde(with = "crate::serde::hex")]
+            background: Color,
+            #[serde(with = "crate::serde::hex8")]
+            overlay: Color,
+            #[serde(with = "crate::serde::rgba")]
+            shadow: Color,
+            #[serde(with = "crate::serde::any")]
+            accent: Color,
+        }
+
+        let theme = Theme {
+            background: Color::rgb(0x1d, 0x20, 0x21),
+            overlay: Color::rgba(0xff, 0x88, 0x00, 0x80),
+            shadow: Color::rgba(0, 0, 0, 0x66),
+            accent: Color::rgb(0xfa, 0xbd, 0x2f),
+        };
+        let json = serde_json::to_string(&theme).unwrap();
+        assert_eq!(
+            json,
+            r##"{"background":"#1d2021","overlay":"#ff880080","shadow":"rgba(0,0,0,0.400)","accent":"#fabd2f"}"##
+        );
+        assert_eq!(serde_json::from_str::<Theme>(&json).unwrap(), theme);
+
+        // every field reads every notation
+        let mixed = r##"{"background":"rgb(29,32,33)","overlay":"0x80ff8800",
+            "shadow":"#00000066","accent":" fa/bd/2f/ff "}"##;
+        assert_eq!(serde_json::from_str::<Theme>(mixed).unwrap(), theme);
+
+        // only `any` takes the struct form
+        let old = r##"{"background":"#1d2021","overlay":"#ff880080","shadow":"#00000066",
+            "accent":{"red":250,"green":189,"blue":47,"alpha":255}}"##;
+        assert_eq!(serde_json::from_str::<Theme>(old).unwrap(), theme);
+        let struct_background = json.replace(
+            r##""#1d2021""##,
+            r#"{"red":29,"green":32,"blue":33,"alpha":255}"#,
+        );
+        let error = serde_json::from_str::<Theme>(&struct_background).unwrap_err();
+        assert!(
+            error.to_string().starts_with("invalid type: map"),
+            "{}",
+            error
+        );
+
+        let bad = json.replace("#fabd2f", "#fabd2");
+        let error = serde_json::from_str::<Theme>(&bad).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with(r##"invalid value: string "#fabd2", expected a color string"##),
+            "{}",
+            error
+        );
+        let bad = json.replace("rgba(0,0,0,0.400)", "rgba(0,0,300,0.400)");
+        assert!(serde_json::from_str::<Theme>(&bad).is_err());
+    }
+
+    #[test]
+    fn rgba_round_trip_tests() {
+        use super::*;
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Layer {
+            #[serde(with = "crate::serde::rgba")]
+            tint: Color,
+        }
+
+        for alpha in 0..=255u8 {
+            let layer = Layer {
+                tint: Color::rgba(10, 20, 30, alpha),
+            };
+            let json = serde_json::to_string(&layer).unwrap();
+            assert_eq!(
+                serde_json::from_str::<Layer>(&json).unwrap(),
+                layer,
+                "{}",
+                json
+            );
+        }
+        assert_eq!(alpha_fraction(0), "0.000");
+        assert_eq!(alpha_fraction(7), "0.028");
+        assert_eq!(alpha_fraction(128), "0.502");
+        assert_eq!(alpha_fraction(255), "1.000");
+    }
+
+    #[test]
+    fn text_tests() {
+        use super::*;
+        use std::collections::BTreeMap;
+
+        let colors: Vec<Text<Hex>> = vec![
+            Color::rgba(1, 2, 3, 4).into(),
+            Color::rgb(255, 255, 255).into(),
+        ];
+        assert_eq!(
+            serde_json::to_string(&colors).unwrap(),
+            r##"["#010203","#ffffff"]"##
+        );
+        let read: Vec<Text<Hex8>> =
+            serde_json::from_str(r##"["#01020304","rgb(1,2,3)"]"##).unwrap();
+        assert_eq!(Color::from(read[0]), Color::rgba(1, 2, 3, 4));
+        assert_eq!(read[1].color, Color::rgb(1, 2, 3));
+
+        let mut named: BTreeMap<String, Text<Any>> = BTreeMap::new();
+        named.insert("clear".to_string(), Text::new(Color::rgba(0, 0, 0, 0)));
+        named.insert("red".to_string(), Text::new(Color::rgb(255, 0, 0)));
+        let json = serde_json::to_string(&named).unwrap();
+        assert_eq!(json, r##"{"clear":"#00000000","red":"#ff0000"}"##);
+        assert_eq!(
+            serde_json::from_str::<BTreeMap<String, Text<Any>>>(&json).unwrap(),
+            named
+        );
+        assert_eq!(
+            serde_json::to_string(&Text::<Rgba>::new(Color::rgba(1, 2, 3, 255))).unwrap(),
+            r#""rgba(1,2,3,1.000)""#
+        );
+        assert!(serde_json::from_str::<Text<Rgba>>(r#""rgb(50%,0,0)""#).is_err());
+        assert!(serde_json::from_str::<Text<Hex>>("12").is_err());
+    }
+}
@@ -0,0 +1,420 @@
+use crate::utils;
+use crate::Color;
+
+const D65_WHITE: [f64; 3] = [0.95047, 1.0, 1.08883];
+
+/// oklab perceptual color space, `l` is 0.0 - 1.0
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Oklab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+/// cie 1976 l*a*b* relative to the d65 white point, `l` is 0.0 - 100.0
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Lab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+/// cylindrical form of oklab, `h` is in degrees 0.0 - 360.0
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Oklch {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32,
+}
+
+/// hue in degrees 0.0 - 360.0, saturation and lightness 0.0 - 1.0
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Hsl {
+    pub h: f32,
+    pub s: f32,
+    pub l: f32,
+}
+
+/// hue in degrees 0.0 - 360.0, saturation and value 0.0 - 1.0
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Hsv {
+    pub h: f32,
+    pub s: f32,
+    pub v: f32,
+}
+
+impl Oklab {
+    pub fn new(l: f32, a: f32, b: f32) -> Self {
+        Oklab { l, a, b }
+    }
+
+    pub fn from_linear_srgb(red: f32, green: f32, blue: f32) -> Self {
+        let (red, green, blue) = (red as f64, green as f64, blue as f64);
+        let l = (0.4122214708 * red + 0.5363325363 * green + 0.0514459929 * blue).cbrt();
+        let m = (0.2119034982 * red + 0.6806995451 * green + 0.1073969566 * blue).cbrt();
+        let s = (0.0883024619 * red + 0.2817188376 * green + 0.6299787005 * blue).cbrt();
+        Oklab {
+            l: (0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s) as f32,
+            a: (1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s) as f32,
+            b: (0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s) as f32,
+        }
+    }
+
+    /// linear srgb channels, not clamped so out of gamut values can be detected
+    pub fn to_linear_srgb(&self) -> (f32, f32, f32) {
+        let (lightness, a, b) = (self.l as f64, self.a as f64, self.b as f64);
+        let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+        let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+        let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+        (
+            (4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s) as f32,
+            (-1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s) as f32,
+            (-0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s) as f32,
+        )
+    }
+
+    pub fn in_gamut(&self) -> bool {
+        let (red, green, blue) = self.to_linear_srgb();
+        let range = -1e-4f32..=1.0001f32;
+        range.contains(&red) && range.contains(&green) && range.contains(&blue)
+    }
+}
+
+impl Oklch {
+    pub fn new(l: f32, c: f32, h: f32) -> Self {
+        Oklch { l, c, h }
+    }
+
+    pub fn in_gamut(&self) -> bool {
+        Oklab::from(*self).in_gamut()
+    }
+
+    /// reduces chroma, keeping lightness and hue, until the color fits in srgb
+    pub fn clamp_chroma(&self) -> Oklch {
+        if self.in_gamut() {
+            return *self;
+        }
+        let mut low = 0.0f32;
+        let mut high = self.c;
+        for _ in 0..24 {
+            let middle = (low + high) / 2.0f32;
+            match Oklch::new(self.l, middle, self.h).in_gamut() {
+                true => low = middle,
+                false => high = middle,
+            }
+        }
+        Oklch::new(self.l, low, self.h)
+    }
+}
+
+impl Hsl {
+    pub fn new(h: f32, s: f32, l: f32) -> Self {
+        Hsl { h, s, l }
+    }
+}
+
+impl Hsv {
+    pub fn new(h: f32, s: f32, v: f32) -> Self {
+        Hsv { h, s, v }
+    }
+}
+
+impl Lab {
+    pub fn new(l: f32, a: f32, b: f32) -> Self {
+        Lab { l, a, b }
+    }
+
+    /// linear srgb channels, not clamped so out of gamut values can be detected
+    pub fn to_linear_srgb(&self) -> (f32, f32, f32) {
+        let fy = (self.l as f64 + 16.0) / 116.0;
+        let fx = fy + self.a as f64 / 500.0;
+        let fz = fy - self.b as f64 / 200.0;
+        let x = lab_f_inverse(fx) * D65_WHITE[0];
+        let y = lab_f_inverse(fy) * D65_WHITE[1];
+        let z = lab_f_inverse(fz) * D65_WHITE[2];
+        (
+            (3.2404542 * x - 1.5371385 * y - 0.4985314 * z) as f32,
+            (-0.9692660 * x + 1.8760108 * y + 0.0415560 * z) as f32,
+            (0.0556434 * x - 0.2040259 * y + 1.0572252 * z) as f32,
+        )
+    }
+}
+
+fn lab_f(t: f64) -> f64 {
+    let delta = 6.0f64 / 29.0f64;
+    match t > delta.powi(3) {
+        true => t.cbrt(),
+        false => t / (3.0f64 * delta * delta) + 4.0f64 / 29.0f64,
+    }
+}
+
+fn lab_f_inverse(t: f64) -> f64 {
+    let delta = 6.0f64 / 29.0f64;
+    match t > delta {
+        true => t.powi(3),
+        false => 3.0f64 * delta * delta * (t - 4.0f64 / 29.0f64),
+    }
+}
+
+impl From<Color> for Lab {
+    fn from(color: Color) -> Self {
+        let red = utils::srgb_to_linear(color.red as f32 / 255.0f32) as f64;
+        let green = utils::srgb_to_linear(color.green as f32 / 255.0f32) as f64;
+        let blue = utils::srgb_to_linear(color.blue as f32 / 255.0f32) as f64;
+        let x = (0.4124564 * red + 0.3575761 * green + 0.1804375 * blue) / D65_WHITE[0];
+        let y = (0.2126729 * red + 0.7151522 * green + 0.0721750 * blue) / D65_WHITE[1];
+        let z = (0.0193339 * red + 0.1191920 * green + 0.9503041 * blue) / D65_WHITE[2];
+        let (fx, fy, fz) = (lab_f(x), lab_f(y), lab_f(z));
+        Lab {
+            l: (116.0 * fy - 16.0) as f32,
+            a: (500.0 * (fx - fy)) as f32,
+            b: (200.0 * (fy - fz)) as f32,
+        }
+    }
+}
+
+/// out of gamut values are clipped, alpha is set to 255
+impl From<Lab> for Color {
+    fn from(lab: Lab) -> Self {
+        let (red, green, blue) = lab.to_linear_srgb();
+        let encode = |channel: f32| {
+            utils::unit_f32_to_u8(utils::linear_to_srgb(channel.clamp(0.0f32, 1.0f32)))
+        };
+        Color {
+            red: encode(red),
+            green: encode(green),
+            blue: encode(blue),
+            alpha: 255,
+        }
+    }
+}
+
+/// through linear srgb without clipping or rounding to 8 bits
+impl From<Lab> for Oklab {
+    fn from(lab: Lab) -> Self {
+        let (red, green, blue) = lab.to_linear_srgb();
+        Oklab::from_linear_srgb(red, green, blue)
+    }
+}
+
+impl From<Color> for Oklab {
+    fn from(color: Color) -> Self {
+        Oklab::from_linear_srgb(
+            utils::srgb_to_linear(color.red as f32 / 255.0f32),
+            utils::srgb_to_linear(color.green as f32 / 255.0f32),
+            utils::srgb_to_linear(color.blue as f32 / 255.0f32),
+        )
+    }
+}
+
+/// out of gamut values are clipped, alpha is set to 255
+impl From<Oklab> for Color {
+    fn from(oklab: Oklab) -> Self {
+        let (red, green, blue) = oklab.to_linear_srgb();
+        Color {
+            red: utils::unit_f32_to_u8(utils::linear_to_srgb(red.clamp(0.0f32, 1.0f32))),
+            green: utils::unit_f32_to_u8(utils::linear_to_srgb(green.clamp(0.0f32, 1.0f32))),
+            blue: utils::unit_f32_to_u8(utils::linear_to_srgb(blue.clamp(0.0f32, 1.0f32))),
+            alpha: 255,
+        }
+    }
+}
+
+impl From<Oklab> for Oklch {
+    fn from(oklab: Oklab) -> Self {
+        let c = (oklab.a * oklab.a + oklab.b * oklab.b).sqrt();
+        let h = oklab.b.atan2(oklab.a).to_degrees().rem_euclid(360.0f32);
+        Oklch { l: oklab.l, c, h }
+    }
+}
+
+impl From<Oklch> for Oklab {
+    fn from(oklch: Oklch) -> Self {
+        let h = oklch.h.to_radians();
+        Oklab {
+            l: oklch.l,
+            a: oklch.c * h.cos(),
+            b: oklch.c * h.sin(),
+        }
+    }
+}
+
+impl From<Color> for Oklch {
+    fn from(color: Color) -> Self {
+        Oklch::from(Oklab::from(color))
+    }
+}
+
+/// out of gamut values are clipped, alpha is set to 255
+impl From<Oklch> for Color {
+    fn from(oklch: Oklch) -> Self {
+        Color::from(Oklab::from(oklch))
+    }
+}
+
+/// hue in degrees, the largest and the smallest channel, all 0.0 - 1.0
+fn hue_max_min(color: Color) -> (f32, f32, f32) {
+    let red = color.red as f32 / 255.0f32;
+    let green = color.green as f32 / 255.0f32;
+    let blue = color.blue as f32 / 255.0f32;
+    let max = red.max(green).max(blue);
+    let min = red.min(green).min(blue);
+    let delta = max - min;
+    let hue = if delta == 0.0f32 {
+        0.0f32
+    } else if max == red {
+        60.0f32 * ((green - blue) / delta).rem_euclid(6.0f32)
+    } else if max == green {
+        60.0f32 * ((blue - red) / delta + 2.0f32)
+    } else {
+        60.0f32 * ((red - green) / delta + 4.0f32)
+    };
+    (hue, max, min)
+}
+
+/// channels out of hue, chroma and the amount added to every channel
+fn from_hue_chroma(hue: f32, chroma: f32, offset: f32) -> Color {
+    let hue = hue.rem_euclid(360.0f32) / 60.0f32;
+    let x = chroma * (1.0f32 - (hue.rem_euclid(2.0f32) - 1.0f32).abs());
+    let (red, green, blue) = match hue as u8 {
+        0 => (chroma, x, 0.0f32),
+        1 => (x, chroma, 0.0f32),
+        2 => (0.0f32, chroma, x),
+        3 => (0.0f32, x, chroma),
+        4 => (x, 0.0f32, chroma),
+        _ => (chroma, 0.0f32, x),
+    };
+    let channel = |value: f32| utils::unit_f32_to_u8((value + offset).clamp(0.0f32, 1.0f32));
+    Color {
+        red: channel(red),
+        green: channel(green),
+        blue: channel(blue),
+        alpha: 255,
+    }
+}
+
+impl From<Color> for Hsl {
+    fn from(color: Color) -> Self {
+        let (h, max, min) = hue_max_min(color);
+        let l = (max + min) / 2.0f32;
+        let s = match max == min {
+            true => 0.0f32,
+            false => (max - min) / (1.0f32 - (2.0f32 * l - 1.0f32).abs()),
+        };
+        Hsl { h, s, l }
+    }
+}
+
+/// alpha is set to 255
+impl From<Hsl> for Color {
+    fn from(hsl: Hsl) -> Self {
+        let s = hsl.s.clamp(0.0f32, 1.0f32);
+        let l = hsl.l.clamp(0.0f32, 1.0f32);
+        let chroma = (1.0f32 - (2.0f32 * l - 1.0f32).abs()) * s;
+        from_hue_chroma(hsl.h, chroma, l - chroma / 2.0f32)
+    }
+}
+
+impl From<Color> for Hsv {
+    fn from(color: Color) -> Self {
+        let (h, max, min) = hue_max_min(color);
+        let s = match max == 0.0f32 {
+            true => 0.0f32,
+            false => (max - min) / max,
+        };
+        Hsv { h, s, v: max }
+    }
+}
+
+/// alpha is set to 255
+impl From<Hsv> for Color {
+    fn from(hsv: Hsv) -> Self {
+        let s = hsv.s.clamp(0.0f32, 1.0f32);
+        let v = hsv.v.clamp(0.0f32, 1.0f32);
+        let chroma = v * s;
+        from_hue_chroma(hsv.h, chroma, v - chroma)
+    }
+}
+
+mod tests {
+    #[test]
+    fn oklab_tests() {
+        use super::*;
+        use crate::traits::*;
+        let white = Oklab::from(Color::from_hex("#ffffff"));
+        assert!((white.l - 1.0f32).abs() < 1e-4);
+        assert!(white.a.abs() < 1e-4 && white.b.abs() < 1e-4);
+
+        // reference value from the oklab blog post
+        let red = Oklab::from(Color::from_hex("#ff0000"));
+        assert!((red.l - 0.62796f32).abs() < 1e-4);
+        assert!((red.a - 0.22486f32).abs() < 1e-4);
+        assert!((red.b - 0.12585f32).abs() < 1e-4);
+    }
+    #[test]
+    fn lab_tests() {
+        use super::*;
+        use crate::traits::*;
+        let white = Lab::from(Color::from_hex("#ffffff"));
+        assert!((white.l - 100.0f32).abs() < 1e-3);
+        assert!(white.a.abs() < 1e-3 && white.b.abs() < 1e-3);
+
+        let red = Lab::from(Color::from_hex("#ff0000"));
+        assert!((red.l - 53.2408f32).abs() < 1e-2);
+        assert!((red.a - 80.0925f32).abs() < 1e-2);
+        assert!((red.b - 67.2032f32).abs() < 1e-2);
+    }
+    #[test]
+    fn round_trip_tests() {
+        use super::*;
+        use crate::traits::*;
+        for hex in ["#ffbcca", "#0fffff", "#123456", "#000000", "#abf788"].iter() {
+            let color = Color::from_hex(hex);
+            assert_eq!(Color::from(Oklab::from(color)), color);
+            assert_eq!(Color::from(Oklch::from(color)), color);
+            assert_eq!(Color::from(Lab::from(color)), color);
+        }
+    }
+    #[test]
+    fn hsl_hsv_tests() {
+        use super::*;
+        use crate::traits::*;
+        let orange = Hsl::from(Color::from_hex("#ff8000"));
+        assert!((orange.h - 30.1176f32).abs() < 1e-3);
+        assert!((orange.s - 1.0f32).abs() < 1e-6);
+        assert!((orange.l - 0.5f32).abs() < 1e-6);
+        let teal = Hsv::from(Color::from_hex("#336666"));
+        assert!((teal.h - 180.0f32).abs() < 1e-3);
+        assert!((teal.s - 0.5f32).abs() < 1e-6);
+        assert!((teal.v - 0.4f32).abs() < 1e-6);
+        assert_eq!(Hsl::from(Color::from_hex("#808080")).s, 0.0f32);
+        assert_eq!(Hsv::from(Color::from_hex("#000000")), Hsv::default());
+        assert_eq!(
+            Color::from(Hsl::new(-120.0f32, 1.0f32, 0.25f32)),
+            Color::from_hex("#000080")
+        );
+        assert_eq!(
+            Color::from(Hsv::new(300.0f32, 1.0f32, 1.0f32)),
+            Color::from_hex("#ff00ff")
+        );
+        for red in (0..=255u8).step_by(15) {
+            for green in (0..=255u8).step_by(17) {
+                for blue in (0..=255u8).step_by(51) {
+                    let color = Color::from_tuple((red, green, blue));
+                    assert_eq!(Color::from(Hsl::from(color)), color);
+                    assert_eq!(Color::from(Hsv::from(color)), color);
+                }
+            }
+        }
+    }
+    #[test]
+    fn gamut_tests() {
+        use super::*;
+        let vivid = Oklch::new(0.9f32, 0.4f32, 264.0f32);
+        assert!(!vivid.in_gamut());
+        let clamped = vivid.clamp_chroma();
+        assert!(clamped.in_gamut());
+        assert!(clamped.c > 0.0f32 && clamped.c < vivid.c);
+        assert!((clamped.h - vivid.h).abs() < f32::EPSILON);
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::format::ColorFormat;
+use crate::scheme::TerminalScheme;
+use crate::traits::*;
+use crate::utils;
+use crate::Color;
+
+/// names a template can refer to
+pub trait ColorSource {
+    fn lookup(&self, name: &str) -> Option<Color>;
+}
+
+/// `background`, `foreground`, `cursor` and `color0` - `color15`
+impl ColorSource for TerminalScheme {
+    fn lookup(&self, name: &str) -> Option<Color> {
+        match name {
+            "background" => Some(self.background),
+            "foreground" => Some(self.foreground),
+            "cursor" => Some(self.cursor),
+            _ => name
+                .strip_prefix("color")
+                .filter(|index| !index.starts_with('0') || *index == "0")
+                .and_then(|index| index.parse::<usize>().ok())
+                .and_then(|index| self.colors.get(index).copied()),
+        }
+    }
+}
+
+impl ColorSource for HashMap<String, Color> {
+    fn lookup(&self, name: &str) -> Option<Color> {
+        self.get(name).copied()
+    }
+}
+
+/// `expression` split on the dots that are not inside parentheses
+fn split_filters(expression: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0usize;
+    for (position, character) in expression.char_indices() {
+        match character {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            '.' if depth == 0 => {
+                parts.push(expression[start..position].trim());
+                start = position + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(expression[start..].trim());
+    parts
+}
+
+fn argument(
+    filter: &str,
+    value: &str,
+    range: std::ops::RangeInclusive<f32>,
+) -> Result<f32, String> {
+    value
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|number| range.contains(number))
+        .ok_or_else(|| {
+            format!(
+                "`{}` takes a number from {} to {}, got `{}`",
+                filter,
+                range.start(),
+                range.end(),
+                value
+            )
+        })
+}
+
+/// the text for one placeholder, `name` then any number of `lighten(percent)`,
+/// `darken(percent)` and `alpha(0.0 - 1.0)` then at most one format
+fn evaluate<S: ColorSource + ?Sized>(expression: &str, source: &S) -> Result<String, String> {
+    let parts = split_filters(expression);
+    let name = parts[0];
+    if name.is_empty() {
+        return Err("empty placeholder".to_string());
+    }
+    let mut color = source
+        .lookup(name)
+        .ok_or_else(|| format!("unknown color `{}`", name))?;
+    let mut format: Option<ColorFormat> = None;
+    for filter in parts[1..].iter() {
+        if let Some(format) = format {
+            return Err(format!("`{}` has to be the last filter", format.name()));
+        }
+        let (filter_name, value) = match filter.split_once('(') {
+            Some((filter_name, rest)) => match rest.strip_suffix(')') {
+                Some(value) => (filter_name.trim(), Some(value)),
+                None => return Err(format!("missing `)` after `{}`", filter)),
+            },
+            None => (*filter, None),
+        };
+        match (filter_name, value) {
+            ("lighten", Some(value)) => {
+                color = color.lighten(argument(filter_name, value, 0.0..=100.0)? / 100.0f32)
+            }
+            ("darken", Some(value)) => {
+                color = color.darken(argument(filter_name, value, 0.0..=100.0)? / 100.0f32)
+            }
+            ("alpha", Some(value)) => {
+                color = color.with_alpha(utils::unit_f32_to_u8(argument(
+                    filter_name,
+                    value,
+                    0.0..=1.0,
+                )?))
+            }
+            ("lighten", None) | ("darken", None) | ("alpha", None) => {
+                return Err(format!("`{}` needs an argument", filter_name))
+            }
+            (_, None) if ColorFormat::from_name(filter_name).is_some() => {
+                format = ColorFormat::from_name(filter_name)
+            }
+            _ => return Err(format!("unknown filter `{}`", filter_name)),
+        }
+    }
+    Ok(color.format_string(format.unwrap_or(ColorFormat::Hex)))
+}
+
+fn error_at(template: &str, position: usize, message: &str) -> Box<dyn Error> {
+    let before = &template[..position];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count()
+        + 1;
+    format!("{} at line {}, column {}", message, line, column).into()
+}
+
+/// replaces every `{name.filter...}` placeholder in `template`, `{{` and `}}`
+/// stand for literal braces
+/// `{color4.lighten(10).rgb}`, `{foreground.alpha(0.8).rgba}`, `{background}`
+/// (hex when no format is given), every `MakeString` method is a format
+pub fn render<S: ColorSource + ?Sized>(
+    template: &str,
+    source: &S,
+) -> Result<String, Box<dyn Error>> {
+    let mut output = String::with_capacity(template.len());
+    let mut index = 0usize;
+    while let Some(offset) = template[index..].find(['{', '}']) {
+        let start = index + offset;
+        output.push_str(&template[index..start]);
+        let rest = &template[start..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            output.push_str(&rest[..1]);
+            index = start + 2;
+            continue;
+        }
+        if rest.starts_with('}') {
+            return Err(error_at(template, start, "unmatched `}`"));
+        }
+        let end = match rest[1..].find(['{', '}']) {
+            Some(end) if rest[1 + end..].starts_with('}') => start + 1 + end,
+            _ => return Err(error_at(template, start, "unclosed `{`")),
+        };
+        let expression = &template[start + 1..end];
+        let text = evaluate(expression, source).map_err(|message| {
+            error_at(
+                template,
+                start,
+                &format!("{} in `{{{}}}`", message, expression),
+            )
+        })?;
+        output.push_str(&text);
+        index = end + 1;
+    }
+    output.push_str(&template[index..]);
+    Ok(output)
+}
+
+mod tests {
+    #[allow(dead_code)]
+    fn scheme() -> crate::scheme::TerminalScheme {
+        use crate::traits::*;
+        let mut colors = [crate::Color::new(); 16];
+        for (index, color) in colors.iter_mut().enumerate() {
+            *color = crate::Color::from_tuple((index as u8 * 16, 128, 255 - index as u8 * 16));
+        }
+        crate::scheme::TerminalScheme {
+            background: crate::Color::from_hex("#102030"),
+            foreground: crate::Color::from_hex("#e0d0c0"),
+            cursor: crate::Color::from_hex("#ffcc11"),
+            colors,
+        }
+    }
+
+    #[test]
+    fn render_tests() {
+        use super::*;
+        let scheme = scheme();
+        let template = "\
+* {{ --bg: {background}; --fg: {foreground.rgb}; }}
+urxvt*background: [80]{background.alpha(0.8).rgba}
+color4: {color4.lighten(10).hex} / {color4.darken(50).hex_stripped}
+xrgba: { cursor . xrgba }
+";
+        assert_eq!(
+            render(template, &scheme).unwrap(),
+            "\
+* { --bg: #102030; --fg: rgb(224,208,192); }
+urxvt*background: [80]rgba(16,32,48,0.8)
+color4: #538dc5 / 204060
+xrgba: ff/cc/11/ff
+"
+        );
+
+        let all = [
+            "hex",
+            "hex_stripped",
+            "hex8",
+            "hex8_stripped",
+            "rgb",
+            "rgb_stripped",
+            "rgb_percentage",
+            "rgb_percentage_rounded",
+            "rgba",
+            "rgba_stripped",
+            "rgba_percentage",
+            "rgba_percentage_rounded",
+            "xrgba",
+        ];
+        for format in all.iter() {
+            let rendered = render(&format!("{{color15.{}}}", format), &scheme).unwrap();
+            assert_eq!(
+                Some(rendered),
+                ColorFormat::from_name(format)
+                    .map(|format| scheme.colors[15].format_string(format))
+            );
+        }
+
+        let mut named = HashMap::new();
+        named.insert("accent".to_string(), Color::from_hex("#ff8800"));
+        assert_eq!(
+            render("{accent.rgb_stripped}", &named).unwrap(),
+            "255,136,0"
+        );
+        assert_eq!(
+            render("no placeholders }} {{", &named).unwrap(),
+            "no placeholders } {"
+        );
+    }
+    #[test]
+    fn error_tests() {
+        use super::*;
+        let scheme = scheme();
+        let message = |template: &str| render(template, &scheme).unwrap_err().to_string();
+        assert_eq!(
+            message("a\nb {colr1}"),
+            "unknown color `colr1` in `{colr1}` at line 2, column 3"
+        );
+        assert_eq!(
+            message("{color16}"),
+            "unknown color `color16` in `{color16}` at line 1, column 1"
+        );
+        assert_eq!(
+            message("{color1.lightn(10)}"),
+            "unknown filter `lightn` in `{color1.lightn(10)}` at line 1, column 1"
+        );
+        assert!(message("{color1.hex.rgb}").starts_with("`hex` has to be the last filter"));
+        assert!(message("{color1.alpha(2)}").starts_with("`alpha` takes a number from 0 to 1"));
+        assert!(message("{color1.lighten}").starts_with("`lighten` needs an argument"));
+        assert!(message("{color1.darken(10}").starts_with("missing `)`"));
+        assert!(message("{}").starts_with("empty placeholder"));
+        assert!(message("x {color1").starts_with("unclosed `{` at line 1, column 3"));
+        assert!(message("x } y").starts_with("unmatched `}`"));
+    }
+}
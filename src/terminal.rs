@@ -0,0 +1,469 @@
+use std::error::Error;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::ansi::parse_osc_reply;
+use crate::ansi::OscReply;
+use crate::contrast::contrast_ratio;
+use crate::generate::Variant;
+use crate::scheme::TerminalScheme;
+use crate::traits::*;
+use crate::Color;
+
+/// a color a terminal lets programs query and change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorSlot {
+    /// osc 4
+    Palette(u8),
+    /// osc 10
+    Foreground,
+    /// osc 11
+    Background,
+    /// osc 12
+    Cursor,
+}
+
+impl ColorSlot {
+    /// osc body up to the color spec
+    fn prefix(&self) -> String {
+        match self {
+            ColorSlot::Palette(index) => format!("4;{}", index),
+            ColorSlot::Foreground => "10".to_string(),
+            ColorSlot::Background => "11".to_string(),
+            ColorSlot::Cursor => "12".to_string(),
+        }
+    }
+
+    /// osc body restoring the default
+    fn reset(&self) -> String {
+        match self {
+            ColorSlot::Palette(index) => format!("104;{}", index),
+            ColorSlot::Foreground => "110".to_string(),
+            ColorSlot::Background => "111".to_string(),
+            ColorSlot::Cursor => "112".to_string(),
+        }
+    }
+
+    fn answered_by(&self, reply: &OscReply) -> Option<Color> {
+        match (self, reply) {
+            (ColorSlot::Palette(index), OscReply::Palette(answered, color))
+                if index == answered =>
+            {
+                Some(*color)
+            }
+            (ColorSlot::Foreground, OscReply::Foreground(color))
+            | (ColorSlot::Background, OscReply::Background(color))
+            | (ColorSlot::Cursor, OscReply::Cursor(color)) => Some(*color),
+            _ => None,
+        }
+    }
+}
+
+/// the 16 palette slots, foreground, background and cursor, in the order
+/// `TerminalScheme` holds them
+fn scheme_slots() -> Vec<ColorSlot> {
+    let mut slots: Vec<ColorSlot> = (0..16).map(ColorSlot::Palette).collect();
+    slots.extend(vec![
+        ColorSlot::Foreground,
+        ColorSlot::Background,
+        ColorSlot::Cursor,
+    ]);
+    slots
+}
+
+/// talks osc color sequences over `io`, usually the controlling tty
+///
+/// the caller owns the tty setup: it has to be in raw mode without echo, and
+/// reads should return after a short wait, `Ok(0)`, `WouldBlock` and `TimedOut`
+/// all count as nothing received yet
+/// every query is followed by a primary device attributes request, which all
+/// terminals answer in order, so a terminal ignoring osc queries costs one
+/// round trip instead of the whole timeout
+pub struct Terminal<T: Read + Write> {
+    io: T,
+    timeout: Duration,
+}
+
+impl<T: Read + Write> Terminal<T> {
+    /// waits up to 100ms for replies
+    pub fn new(io: T) -> Self {
+        Terminal::with_timeout(io, Duration::from_millis(100))
+    }
+
+    pub fn with_timeout(io: T, timeout: Duration) -> Self {
+        Terminal { io, timeout }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.io
+    }
+
+    /// the current color of every slot in `slots`, `None` where the terminal
+    /// did not answer
+    pub fn query(&mut self, slots: &[ColorSlot]) -> Result<Vec<Option<Color>>, Box<dyn Error>> {
+        let mut request = String::new();
+        for slot in slots.iter() {
+            request.push_str(&format!("\x1b]{};?\x1b\\", slot.prefix()));
+        }
+        request.push_str("\x1b[c");
+        self.io.write_all(request.as_bytes())?;
+        self.io.flush()?;
+
+        let mut colors = vec![None; slots.len()];
+        let mut pending: Vec<u8> = Vec::new();
+        let mut buffer = [0u8; 256];
+        let deadline = Instant::now() + self.timeout;
+        while Instant::now() < deadline {
+            let count = match self.io.read(&mut buffer) {
+                Ok(count) => count,
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted
+                    ) =>
+                {
+                    0
+                }
+                Err(error) => return Err(error.into()),
+            };
+            if count == 0 {
+                std::thread::sleep(Duration::from_millis(1));
+                continue;
+            }
+            pending.extend_from_slice(&buffer[..count]);
+            let (replies, attributes) = drain_replies(&mut pending);
+            for reply in replies.iter() {
+                for (slot, color) in slots.iter().zip(colors.iter_mut()) {
+                    if let Some(answer) = slot.answered_by(reply) {
+                        *color = Some(answer);
+                    }
+                }
+            }
+            if attributes {
+                break;
+            }
+        }
+        Ok(colors)
+    }
+
+    pub fn foreground(&mut self) -> Result<Option<Color>, Box<dyn Error>> {
+        Ok(self.query(&[ColorSlot::Foreground])?[0])
+    }
+
+    pub fn background(&mut self) -> Result<Option<Color>, Box<dyn Error>> {
+        Ok(self.query(&[ColorSlot::Background])?[0])
+    }
+
+    /// whether the background is dark or light, for picking a theme
+    pub fn background_variant(&mut self) -> Result<Option<Variant>, Box<dyn Error>> {
+        let white = Color::from_tuple((255, 255, 255));
+        Ok(self.background()?.map(|background| {
+            match contrast_ratio(white, background) > contrast_ratio(Color::new(), background) {
+                true => Variant::Dark,
+                false => Variant::Light,
+            }
+        }))
+    }
+
+    /// color0 - color15, foreground, background and cursor in one round trip,
+    /// `None` unless the terminal answered for all of them
+    pub fn scheme(&mut self) -> Result<Option<TerminalScheme>, Box<dyn Error>> {
+        let colors = self.query(&scheme_slots())?;
+        if colors.iter().any(Option::is_none) {
+            return Ok(None);
+        }
+        let colors: Vec<Color> = colors.into_iter().flatten().collect();
+        let mut scheme = TerminalScheme {
+            foreground: colors[16],
+            background: colors[17],
+            cursor: colors[18],
+            ..TerminalScheme::default()
+        };
+        scheme.colors.copy_from_slice(&colors[..16]);
+        Ok(Some(scheme))
+    }
+
+    pub fn set(&mut self, slot: ColorSlot, color: Color) -> Result<(), Box<dyn Error>> {
+        self.send(&[(slot, Some(color))])
+    }
+
+    /// back to the terminal's configured color
+    pub fn reset(&mut self, slot: ColorSlot) -> Result<(), Box<dyn Error>> {
+        self.send(&[(slot, None)])
+    }
+
+    pub fn set_scheme(&mut self, scheme: &TerminalScheme) -> Result<(), Box<dyn Error>> {
+        let mut colors = scheme.colors.to_vec();
+        colors.extend(vec![scheme.foreground, scheme.background, scheme.cursor]);
+        let changes: Vec<(ColorSlot, Option<Color>)> = scheme_slots()
+            .into_iter()
+            .zip(colors.into_iter().map(Some))
+            .collect();
+        self.send(&changes)
+    }
+
+    /// restores the whole palette, foreground, background and cursor
+    pub fn reset_scheme(&mut self) -> Result<(), Box<dyn Error>> {
+        self.io
+            .write_all(b"\x1b]104\x1b\\\x1b]110\x1b\\\x1b]111\x1b\\\x1b]112\x1b\\")?;
+        self.io.flush()?;
+        Ok(())
+    }
+
+    /// one write for all `changes`, `None` resets the slot
+    fn send(&mut self, changes: &[(ColorSlot, Option<Color>)]) -> Result<(), Box<dyn Error>> {
+        let mut request = String::new();
+        for (slot, color) in changes.iter() {
+            let body = match color {
+                Some(color) => format!(
+                    "{};rgb:{:02x}/{:02x}/{:02x}",
+                    slot.prefix(),
+                    color.red,
+                    color.green,
+                    color.blue
+                ),
+                None => slot.reset(),
+            };
+            request.push_str(&format!("\x1b]{}\x1b\\", body));
+        }
+        self.io.write_all(request.as_bytes())?;
+        self.io.flush()?;
+        Ok(())
+    }
+}
+
+/// removes every complete osc reply and device attributes reply from the
+/// front of `pending`, incomplete sequences are kept for the next read
+/// returns the parsed osc replies and whether device attributes arrived
+fn drain_replies(pending: &mut Vec<u8>) -> (Vec<OscReply>, bool) {
+    let mut replies = Vec::new();
+    let mut attributes = false;
+    let mut position = 0usize;
+    while let Some(offset) = pending[position..].iter().position(|byte| *byte == 0x1b) {
+        let start = position + offset;
+        match pending.get(start + 1) {
+            Some(b']') => {
+                let body = start + 2;
+                let end = pending[body..]
+                    .iter()
+                    .enumerate()
+                    .find(|(index, byte)| {
+                        **byte == 0x07
+                            || (**byte == 0x1b && pending.get(body + index + 1) == Some(&b'\\'))
+                    })
+                    .map(|(index, byte)| (body + index, *byte));
+                let (end, terminator) = match end {
+                    Some(end) => end,
+                    None => break,
+                };
+                let text = String::from_utf8_lossy(&pending[body..end]);
+                if let Ok(reply) = parse_osc_reply(&text) {
+                    replies.push(reply);
+                }
+                position = match terminator {
+                    0x07 => end + 1,
+                    _ => end + 2,
+                };
+            }
+            Some(b'[') => {
+                let parameters = start + 2;
+                let end = match pending[parameters..]
+                    .iter()
+                    .position(|byte| (0x40..=0x7e).contains(byte))
+                {
+                    Some(offset) => parameters + offset,
+                    None => break,
+                };
+                if pending[end] == b'c' && pending.get(parameters) == Some(&b'?') {
+                    attributes = true;
+                }
+                position = end + 1;
+            }
+            Some(_) => position = start + 2,
+            None => break,
+        }
+    }
+    pending.drain(..position);
+    (replies, attributes)
+}
+
+mod tests {
+    /// answers osc 4, 10, 11 and 12 queries out of `scheme`, and device
+    /// attributes when `attributes` is set, `chunk` bytes per read
+    #[allow(dead_code)]
+    pub struct FakeTerminal {
+        pub scheme: crate::scheme::TerminalScheme,
+        pub attributes: bool,
+        pub chunk: usize,
+        pub written: Vec<u8>,
+        pub replies: std::collections::VecDeque<u8>,
+    }
+
+    impl std::io::Write for FakeTerminal {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+            self.written.extend_from_slice(bytes);
+            let text = String::from_utf8_lossy(bytes).to_string();
+            for request in text.split("\x1b\\") {
+                let (body, attributes) = match request.split_once("\x1b[c") {
+                    Some((body, _)) => (body, true),
+                    None => (request, false),
+                };
+                if let Some(query) = body
+                    .strip_prefix("\x1b]")
+                    .and_then(|body| body.strip_suffix(";?"))
+                {
+                    let color = match query {
+                        "10" => Some(self.scheme.foreground),
+                        "11" => Some(self.scheme.background),
+                        "12" => Some(self.scheme.cursor),
+                        _ => query
+                            .strip_prefix("4;")
+                            .and_then(|index| index.parse::<usize>().ok())
+                            .and_then(|index| self.scheme.colors.get(index).copied()),
+                    };
+                    if let Some(color) = color {
+                        // alternate terminators like real terminals mirroring the query
+                        let terminator = match query.len() % 2 {
+                            0 => "\x07",
+                            _ => "\x1b\\",
+                        };
+                        let reply = format!(
+                            "\x1b]{};rgb:{:02x}{:02x}/{:02x}{:02x}/{:02x}{:02x}{}",
+                            query,
+                            color.red,
+                            color.red,
+                            color.green,
+                            color.green,
+                            color.blue,
+                            color.blue,
+                            terminator
+                        );
+                        self.replies.extend(reply.bytes());
+                    }
+                }
+                if attributes && self.attributes {
+                    self.replies.extend(b"\x1b[?62;22c".iter());
+                }
+            }
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl std::io::Read for FakeTerminal {
+        fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+            if self.replies.is_empty() {
+                return Err(std::io::ErrorKind::WouldBlock.into());
+            }
+            let count = buffer.len().min(self.chunk).min(self.replies.len());
+            for byte in buffer.iter_mut().take(count) {
+                *byte = self.replies.pop_front().unwrap();
+            }
+            Ok(count)
+        }
+    }
+
+    #[allow(dead_code)]
+    fn fake(attributes: bool) -> FakeTerminal {
+        use crate::traits::*;
+        let mut scheme = crate::scheme::TerminalScheme {
+            background: crate::Color::from_hex("#1e1e2e"),
+            foreground: crate::Color::from_hex("#cdd6f4"),
+            cursor: crate::Color::from_hex("#f5e0dc"),
+            ..Default::default()
+        };
+        for (index, color) in scheme.colors.iter_mut().enumerate() {
+            *color = crate::Color::from_tuple((index as u8 * 16, 0, 255 - index as u8));
+        }
+        FakeTerminal {
+            scheme,
+            attributes,
+            chunk: 5,
+            written: Vec::new(),
+            replies: Default::default(),
+        }
+    }
+
+    #[test]
+    fn query_tests() {
+        use super::*;
+        let expected = fake(true).scheme;
+        let mut terminal = Terminal::with_timeout(fake(true), Duration::from_secs(10));
+        let started = Instant::now();
+        assert_eq!(terminal.background().unwrap(), Some(expected.background));
+        assert_eq!(terminal.background_variant().unwrap(), Some(Variant::Dark));
+        assert_eq!(terminal.scheme().unwrap(), Some(expected));
+        assert_eq!(
+            terminal
+                .query(&[ColorSlot::Palette(3), ColorSlot::Palette(200)])
+                .unwrap(),
+            vec![Some(expected.colors[3]), None]
+        );
+        // device attributes ends every query long before the timeout
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let written = terminal.into_inner().written;
+        assert!(written.starts_with(b"\x1b]11;?\x1b\\\x1b[c"));
+
+        // without device attributes the timeout is the only way out
+        let mut terminal = Terminal::with_timeout(fake(false), Duration::from_millis(20));
+        assert_eq!(terminal.foreground().unwrap(), Some(expected.foreground));
+        let mut terminal = Terminal::with_timeout(fake(false), Duration::from_millis(20));
+        assert_eq!(terminal.scheme().unwrap(), Some(expected));
+        let mut terminal = Terminal::with_timeout(fake(false), Duration::from_millis(20));
+        assert_eq!(
+            terminal.query(&[ColorSlot::Palette(16)]).unwrap(),
+            vec![None]
+        );
+    }
+
+    #[test]
+    fn set_tests() {
+        use super::*;
+        let mut terminal = Terminal::new(fake(true));
+        terminal
+            .set(ColorSlot::Palette(1), Color::from_hex("#ff8000"))
+            .unwrap();
+        terminal.reset(ColorSlot::Palette(1)).unwrap();
+        terminal.reset(ColorSlot::Background).unwrap();
+        assert_eq!(
+            terminal.into_inner().written,
+            b"\x1b]4;1;rgb:ff/80/00\x1b\\\x1b]104;1\x1b\\\x1b]111\x1b\\".to_vec()
+        );
+
+        let scheme = fake(true).scheme;
+        let mut terminal = Terminal::new(fake(true));
+        terminal.set_scheme(&scheme).unwrap();
+        terminal.reset_scheme().unwrap();
+        let written = String::from_utf8(terminal.into_inner().written).unwrap();
+        assert!(written.starts_with("\x1b]4;0;rgb:00/00/ff\x1b\\\x1b]4;1;rgb:10/00/fe\x1b\\"));
+        assert!(written.contains(
+            "\x1b]4;15;rgb:f0/00/f0\x1b\\\x1b]10;rgb:cd/d6/f4\x1b\\\x1b]11;rgb:1e/1e/2e\x1b\\\x1b]12;rgb:f5/e0/dc\x1b\\"
+        ));
+        assert!(written.ends_with("\x1b]104\x1b\\\x1b]110\x1b\\\x1b]111\x1b\\\x1b]112\x1b\\"));
+    }
+
+    #[test]
+    fn drain_tests() {
+        use super::*;
+        let mut pending = b"\x1b]11;rgb:0000/0000/0000\x07\x1b]10;rgb:ffff/ffff/ffff\x1b\\\x1b[?6c\x1b]4;1;rgb:ff"
+            .to_vec();
+        let (replies, attributes) = drain_replies(&mut pending);
+        assert_eq!(replies.len(), 2);
+        assert!(attributes);
+        assert_eq!(pending, b"\x1b]4;1;rgb:ff".to_vec());
+        pending.extend_from_slice(b"/00/00\x07");
+        let (replies, attributes) = drain_replies(&mut pending);
+        assert_eq!(
+            replies,
+            vec![OscReply::Palette(1, Color::from_hex("#ff0000"))]
+        );
+        assert!(!attributes);
+        assert!(pending.is_empty());
+    }
+}
@@ -1,3 +1,9 @@
+use std::fmt;
+use std::fmt::Write;
+
+use crate::format;
+use crate::format::ColorFormat;
+use crate::format::FormatDisplay;
 use crate::utils;
 
 pub trait ToTuple {
@@ -101,100 +107,77 @@ pub trait MakeString
 where
     Self: ToTuple,
 {
+    /// writes `self` in `format` without allocating
+    fn write_format<W: Write + ?Sized>(&self, writer: &mut W, format: ColorFormat) -> fmt::Result {
+        format::write_format(writer, self, format)
+    }
+    fn format_string(&self, format: ColorFormat) -> String {
+        let mut string = String::new();
+        self.write_format(&mut string, format).unwrap();
+        string
+    }
+    fn display_format(&self, format: ColorFormat) -> FormatDisplay<'_, Self> {
+        FormatDisplay::new(self, format)
+    }
+    fn display_hex(&self) -> FormatDisplay<'_, Self> {
+        self.display_format(ColorFormat::Hex)
+    }
+    fn display_hex8(&self) -> FormatDisplay<'_, Self> {
+        self.display_format(ColorFormat::Hex8)
+    }
+    fn display_rgb(&self) -> FormatDisplay<'_, Self> {
+        self.display_format(ColorFormat::Rgb)
+    }
+    fn display_rgb_percentage(&self) -> FormatDisplay<'_, Self> {
+        self.display_format(ColorFormat::RgbPercentage)
+    }
+    fn display_rgba(&self) -> FormatDisplay<'_, Self> {
+        self.display_format(ColorFormat::Rgba)
+    }
+    fn display_rgba_percentage(&self) -> FormatDisplay<'_, Self> {
+        self.display_format(ColorFormat::RgbaPercentage)
+    }
+    fn display_xrgba(&self) -> FormatDisplay<'_, Self> {
+        self.display_format(ColorFormat::Xrgba)
+    }
     fn hex(&self) -> String {
-        let tuple = self.to_tuple();
-        format!("#{:02x}{:02x}{:02x}", tuple.0, tuple.1, tuple.2)
+        self.format_string(ColorFormat::Hex)
     }
     fn hex_stripped(&self) -> String {
-        let tuple = self.to_tuple();
-        format!("{:02x}{:02x}{:02x}", tuple.0, tuple.1, tuple.2)
+        self.format_string(ColorFormat::HexStripped)
     }
     fn hex8(&self) -> String {
-        let tuple = self.to_tuple_alpha();
-        format!(
-            "#{:02x}{:02x}{:02x}{:02x}",
-            tuple.0, tuple.1, tuple.2, tuple.3
-        )
+        self.format_string(ColorFormat::Hex8)
     }
     fn hex8_stripped(&self) -> String {
-        let tuple = self.to_tuple_alpha();
-        format!(
-            "{:02x}{:02x}{:02x}{:02x}",
-            tuple.0, tuple.1, tuple.2, tuple.3
-        )
+        self.format_string(ColorFormat::Hex8Stripped)
     }
     fn rgb(&self) -> String {
-        let tuple = self.to_tuple();
-        format!("rgb({},{},{})", tuple.0, tuple.1, tuple.2)
+        self.format_string(ColorFormat::Rgb)
     }
     fn rgb_stripped(&self) -> String {
-        let tuple = self.to_tuple();
-        format!("{},{},{}", tuple.0, tuple.1, tuple.2)
+        self.format_string(ColorFormat::RgbStripped)
     }
     fn rgb_percentage(&self) -> String {
-        let tuple = self.to_tuple();
-        format!(
-            "rgb({}%,{}%,{}%)",
-            utils::u8_to_percentage(tuple.0),
-            utils::u8_to_percentage(tuple.1),
-            utils::u8_to_percentage(tuple.2),
-        )
+        self.format_string(ColorFormat::RgbPercentage)
     }
     fn rgb_percentage_rounded(&self) -> String {
-        let tuple = self.to_tuple();
-        format!(
-            "rgb({}%,{}%,{}%)",
-            utils::u8_to_percentage_rounded(tuple.0),
-            utils::u8_to_percentage_rounded(tuple.1),
-            utils::u8_to_percentage_rounded(tuple.2),
-        )
+        self.format_string(ColorFormat::RgbPercentageRounded)
     }
     fn rgba(&self) -> String {
-        let tuple = self.to_tuple_alpha();
-        format!(
-            "rgba({},{},{},{})",
-            tuple.0,
-            tuple.1,
-            tuple.2,
-            utils::u8_to_f32_clamped(tuple.3)
-        )
+        self.format_string(ColorFormat::Rgba)
     }
     fn rgba_stripped(&self) -> String {
-        let tuple = self.to_tuple_alpha();
-        format!(
-            "{},{},{},{}",
-            tuple.0,
-            tuple.1,
-            tuple.2,
-            utils::u8_to_f32_clamped(tuple.3)
-        )
+        self.format_string(ColorFormat::RgbaStripped)
     }
     fn rgba_percentage(&self) -> String {
-        let tuple = self.to_tuple_alpha();
-        format!(
-            "rgba({}%,{}%,{}%,{})",
-            utils::u8_to_percentage(tuple.0),
-            utils::u8_to_percentage(tuple.1),
-            utils::u8_to_percentage(tuple.2),
-            utils::u8_to_f32_clamped(tuple.3),
-        )
+        self.format_string(ColorFormat::RgbaPercentage)
     }
     fn rgba_percentage_rounded(&self) -> String {
-        let tuple = self.to_tuple_alpha();
-        format!(
-            "rgba({}%,{}%,{}%,{})",
-            utils::u8_to_percentage_rounded(tuple.0),
-            utils::u8_to_percentage_rounded(tuple.1),
-            utils::u8_to_percentage_rounded(tuple.2),
-            utils::u8_to_f32_clamped(tuple.3),
-        )
+        self.format_string(ColorFormat::RgbaPercentageRounded)
     }
     fn xrgba(&self) -> String {
-        let tuple = self.to_tuple_alpha();
-        format!(
-            "{:02x}/{:02x}/{:02x}/{:02x}",
-            tuple.0, tuple.1, tuple.2, tuple.3
-        )
+        self.format_string(ColorFormat::Xrgba)
     }
 }
 
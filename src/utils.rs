@@ -11,6 +11,9 @@ pub static XRGBA_REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r#"[a-fA-F0-9]{2}/[a-fA-F0-9]{2}/[a-fA-F0-9]{2}/[a-fA-F0-9]{2}"#).unwrap()
 });
 
+pub static HEX_U32_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"0[xX](?:[a-fA-F0-9]{8}|[a-fA-F0-9]{6})"#).unwrap());
+
 pub static RGB_REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r#"[rR][gG][bB]\(((?:\d{1,3}\.?)?\d{1,3}%?),\s*?((?:\d{1,3}\.?)?\d{1,3}%?),\s*?((?:\d{1,3}\.?)?\d{1,3}%?)\)"#).unwrap()
 });
@@ -27,6 +30,7 @@ pub static RGBA_REGEX: Lazy<Regex> = Lazy::new(|| {
 /// 1 - rgb
 /// 2 - rgba
 /// 3 - xrgba
+/// 4 - 0xRRGGBB or 0xAARRGGBB
 /// 9 - unsupported
 pub fn classify(s: &str) -> u8 {
     if HEXCOLOR_REGEX.is_match(s) {
@@ -37,11 +41,31 @@ pub fn classify(s: &str) -> u8 {
         2u8
     } else if XRGBA_REGEX.is_match(s) {
         3u8
+    } else if HEX_U32_REGEX.is_match(s) {
+        4u8
     } else {
         9u8
     }
 }
 
+/// like `classify` but `s` has to be exactly one color that passes the matching
+/// `check_*` function, so the `*_to_tuple` function can not panic on it
+pub fn classify_strict(s: &str) -> Result<u8, Box<dyn Error>> {
+    let whole = |regex: &Regex| {
+        regex
+            .find(s)
+            .is_some_and(|found| found.start() == 0 && found.end() == s.len())
+    };
+    match classify(s) {
+        0 if whole(&HEXCOLOR_REGEX) => Ok(0u8),
+        1 if whole(&RGB_REGEX) && check_rgb(s) => Ok(1u8),
+        2 if whole(&RGBA_REGEX) && check_rgba(s) => Ok(2u8),
+        3 if whole(&XRGBA_REGEX) => Ok(3u8),
+        4 if whole(&HEX_U32_REGEX) => Ok(4u8),
+        _ => Err(format!("Unrecognized color format: {}", s).into()),
+    }
+}
+
 pub fn check_hex(hex: &str) -> bool {
     HEXCOLOR_REGEX.is_match(hex)
 }
@@ -80,6 +104,10 @@ pub fn check_xrgba(xrgba: &str) -> bool {
     XRGBA_REGEX.is_match(xrgba)
 }
 
+pub fn check_hex_u32(hex: &str) -> bool {
+    HEX_U32_REGEX.is_match(hex)
+}
+
 pub fn check_rgb(rgb: &str) -> bool {
     match RGB_REGEX.is_match(rgb) {
         true => {
@@ -237,38 +265,38 @@ pub fn hex_to_tuple_alpha(hex: &str) -> (u8, u8, u8, u8) {
 pub fn rgb_to_tuple(rgb: &str) -> (u8, u8, u8) {
     assert!(check_rgb(rgb), "Invalid rgb: {}", rgb);
     let capture = RGB_REGEX.captures(rgb).unwrap();
-    let red = match &capture[1].ends_with('%') {
+    let red = match capture[1].ends_with('%') {
         true => {
-            let percentage = (&capture)[1]
+            let percentage = capture[1]
                 .strip_suffix('%')
                 .unwrap()
                 .parse::<f32>()
                 .unwrap();
             percentage_to_u8(percentage)
         }
-        false => (&capture[1]).parse::<u8>().unwrap(),
+        false => capture[1].parse::<u8>().unwrap(),
     };
-    let green = match &capture[2].ends_with('%') {
+    let green = match capture[2].ends_with('%') {
         true => {
-            let percentage = (&capture)[2]
+            let percentage = capture[2]
                 .strip_suffix('%')
                 .unwrap()
                 .parse::<f32>()
                 .unwrap();
             percentage_to_u8(percentage)
         }
-        false => (&capture[2]).parse::<u8>().unwrap(),
+        false => capture[2].parse::<u8>().unwrap(),
     };
-    let blue = match &capture[3].ends_with('%') {
+    let blue = match capture[3].ends_with('%') {
         true => {
-            let percentage = (&capture)[3]
+            let percentage = capture[3]
                 .strip_suffix('%')
                 .unwrap()
                 .parse::<f32>()
                 .unwrap();
             percentage_to_u8(percentage)
         }
-        false => (&capture[3]).parse::<u8>().unwrap(),
+        false => capture[3].parse::<u8>().unwrap(),
     };
     (red, green, blue)
 }
@@ -276,40 +304,40 @@ pub fn rgb_to_tuple(rgb: &str) -> (u8, u8, u8) {
 pub fn rgba_to_tuple_alpha(rgba: &str) -> (u8, u8, u8, u8) {
     assert!(check_rgba(rgba), "Invalid rgba: {}", rgba);
     let capture = RGBA_REGEX.captures(rgba).unwrap();
-    let red = match &capture[1].ends_with('%') {
+    let red = match capture[1].ends_with('%') {
         true => {
-            let percentage = (&capture)[1]
+            let percentage = capture[1]
                 .strip_suffix('%')
                 .unwrap()
                 .parse::<f32>()
                 .unwrap();
             percentage_to_u8(percentage)
         }
-        false => (&capture[1]).parse::<u8>().unwrap(),
+        false => capture[1].parse::<u8>().unwrap(),
     };
-    let green = match &capture[2].ends_with('%') {
+    let green = match capture[2].ends_with('%') {
         true => {
-            let percentage = (&capture)[2]
+            let percentage = capture[2]
                 .strip_suffix('%')
                 .unwrap()
                 .parse::<f32>()
                 .unwrap();
             percentage_to_u8(percentage)
         }
-        false => (&capture[2]).parse::<u8>().unwrap(),
+        false => capture[2].parse::<u8>().unwrap(),
     };
-    let blue = match &capture[3].ends_with('%') {
+    let blue = match capture[3].ends_with('%') {
         true => {
-            let percentage = (&capture)[3]
+            let percentage = capture[3]
                 .strip_suffix('%')
                 .unwrap()
                 .parse::<f32>()
                 .unwrap();
             percentage_to_u8(percentage)
         }
-        false => (&capture[3]).parse::<u8>().unwrap(),
+        false => capture[3].parse::<u8>().unwrap(),
     };
-    let alpha = (&capture)[4].parse::<f32>().unwrap();
+    let alpha = capture[4].parse::<f32>().unwrap();
     let alpha = clamped_f32_to_u8(alpha);
     (red, green, blue, alpha)
 }
@@ -317,18 +345,53 @@ pub fn rgba_to_tuple_alpha(rgba: &str) -> (u8, u8, u8, u8) {
 pub fn xrgba_to_tuple(xrgba: &str) -> (u8, u8, u8) {
     assert!(check_xrgba(xrgba), "Invalid xrgba: {}", xrgba);
     let mut chunks = xrgba.split('/');
-    let red = u8::from_str_radix(&chunks.next().unwrap(), 16).unwrap();
-    let green = u8::from_str_radix(&chunks.next().unwrap(), 16).unwrap();
-    let blue = u8::from_str_radix(&chunks.next().unwrap(), 16).unwrap();
+    let red = u8::from_str_radix(chunks.next().unwrap(), 16).unwrap();
+    let green = u8::from_str_radix(chunks.next().unwrap(), 16).unwrap();
+    let blue = u8::from_str_radix(chunks.next().unwrap(), 16).unwrap();
     (red, green, blue)
 }
 
 pub fn xrgba_to_tuple_alpha(xrgba: &str) -> (u8, u8, u8, u8) {
     assert!(check_xrgba(xrgba), "Invalid xrgba: {}", xrgba);
     let mut chunks = xrgba.split('/');
-    let red = u8::from_str_radix(&chunks.next().unwrap(), 16).unwrap();
-    let green = u8::from_str_radix(&chunks.next().unwrap(), 16).unwrap();
-    let blue = u8::from_str_radix(&chunks.next().unwrap(), 16).unwrap();
-    let alpha = u8::from_str_radix(&chunks.next().unwrap(), 16).unwrap();
+    let red = u8::from_str_radix(chunks.next().unwrap(), 16).unwrap();
+    let green = u8::from_str_radix(chunks.next().unwrap(), 16).unwrap();
+    let blue = u8::from_str_radix(chunks.next().unwrap(), 16).unwrap();
+    let alpha = u8::from_str_radix(chunks.next().unwrap(), 16).unwrap();
     (red, green, blue, alpha)
 }
+
+/// `0xRRGGBB` is opaque, `0xAARRGGBB` puts alpha first
+pub fn hex_u32_to_tuple_alpha(hex: &str) -> (u8, u8, u8, u8) {
+    assert!(check_hex_u32(hex), "Invalid 0x hex: {}", hex);
+    let digits = &HEX_U32_REGEX.find(hex).unwrap().as_str()[2..];
+    let value = u32::from_str_radix(digits, 16).unwrap();
+    let [alpha, red, green, blue] = value.to_be_bytes();
+    match digits.len() {
+        6 => (red, green, blue, 255),
+        _ => (red, green, blue, alpha),
+    }
+}
+
+/// removes the srgb transfer function from a 0.0 - 1.0 channel
+pub fn srgb_to_linear(num: f32) -> f32 {
+    if num <= 0.04045f32 {
+        num / 12.92f32
+    } else {
+        ((num + 0.055f32) / 1.055f32).powf(2.4f32)
+    }
+}
+
+/// applies the srgb transfer function to a 0.0 - 1.0 linear channel
+pub fn linear_to_srgb(num: f32) -> f32 {
+    if num <= 0.0031308f32 {
+        num * 12.92f32
+    } else {
+        1.055f32 * num.powf(1.0f32 / 2.4f32) - 0.055f32
+    }
+}
+
+/// converts 0.0 - 1.0 to 0 - 255 with rounding, unlike `clamped_f32_to_u8`
+pub fn unit_f32_to_u8(num: f32) -> u8 {
+    (num.clamp(0.0f32, 1.0f32) * 255.0f32).round() as u8
+}